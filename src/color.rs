@@ -0,0 +1,233 @@
+use std::str::FromStr;
+
+use crate::theme::Rgb;
+
+/// Whether to colour text output, from `--color`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorChoice {
+    /// Colour a terminal, unless `NO_COLOR` is set.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(format!("Unknown color choice: {}", s)),
+        }
+    }
+}
+
+/// How many colours the terminal text is drawn to can show. Theme colours are
+/// mapped to the nearest one it has.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum ColorDepth {
+    /// Plain text, with no escape codes.
+    #[default]
+    None,
+    /// The 16 standard ANSI colours.
+    Ansi16,
+    /// The xterm 256 colour palette.
+    Ansi256,
+    /// 24-bit colour.
+    TrueColor,
+}
+
+impl ColorDepth {
+    /// The depth to draw to stdout with, from the choice on the command line,
+    /// whether stdout is a terminal, and the environment.
+    pub fn detect(choice: ColorChoice, is_terminal: bool) -> Self {
+        let var = |name| std::env::var(name).ok();
+        Self::from_env(
+            choice,
+            is_terminal,
+            var("NO_COLOR").as_deref(),
+            var("COLORTERM").as_deref(),
+            var("TERM").as_deref(),
+        )
+    }
+
+    fn from_env(
+        choice: ColorChoice,
+        is_terminal: bool,
+        no_color: Option<&str>,
+        colorterm: Option<&str>,
+        term: Option<&str>,
+    ) -> Self {
+        match choice {
+            ColorChoice::Never => return ColorDepth::None,
+            ColorChoice::Always => {}
+            ColorChoice::Auto => {
+                // https://no-color.org: set to anything but empty to turn colour off.
+                let no_color = no_color.is_some_and(|value| !value.is_empty());
+                if no_color || !is_terminal || term == Some("dumb") {
+                    return ColorDepth::None;
+                }
+            }
+        }
+
+        if matches!(colorterm, Some("truecolor" | "24bit")) {
+            ColorDepth::TrueColor
+        } else if term.is_some_and(|term| term.contains("256color")) {
+            ColorDepth::Ansi256
+        } else {
+            ColorDepth::Ansi16
+        }
+    }
+
+    /// Wrap text in the escape codes to draw it in a colour, or the nearest one
+    /// this depth has.
+    pub fn paint(self, text: &str, color: Rgb) -> String {
+        let (r, g, b) = color;
+        match self {
+            ColorDepth::None => text.to_string(),
+            ColorDepth::Ansi16 => {
+                let index = nearest_ansi16(color);
+                // The bright colours have codes of their own, away from the first eight.
+                let code = if index < 8 { 30 + index } else { 82 + index };
+                format!("\x1b[{}m{}\x1b[0m", code, text)
+            }
+            ColorDepth::Ansi256 => format!("\x1b[38;5;{}m{}\x1b[0m", nearest_ansi256(color), text),
+            ColorDepth::TrueColor => format!("\x1b[38;2;{};{};{}m{}\x1b[0m", r, g, b, text),
+        }
+    }
+}
+
+/// xterm's default values for the 16 ANSI colours.
+const ANSI16: [Rgb; 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// The levels of each channel in the 6x6x6 colour cube of the 256 colour
+/// palette.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn distance((r1, g1, b1): Rgb, (r2, g2, b2): Rgb) -> u32 {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+    d(r1, r2) + d(g1, g2) + d(b1, b2)
+}
+
+fn nearest_ansi16(color: Rgb) -> u8 {
+    (0..16)
+        .min_by_key(|&i| distance(color, ANSI16[i as usize]))
+        .unwrap_or(0)
+}
+
+/// The nearest colour from the cube or the grey ramp of the 256 colour palette.
+/// The first 16 are left out, as terminals often change them.
+fn nearest_ansi256(color: Rgb) -> u8 {
+    let level = |c: u8| {
+        (0..6)
+            .min_by_key(|&i| (CUBE_LEVELS[i] as i32 - c as i32).abs())
+            .unwrap_or(0)
+    };
+    let (r, g, b) = (level(color.0), level(color.1), level(color.2));
+    let cube = (CUBE_LEVELS[r], CUBE_LEVELS[g], CUBE_LEVELS[b]);
+    let cube_index = 16 + 36 * r + 6 * g + b;
+
+    // The grey ramp runs from 8 to 238 in steps of 10.
+    let mean = (color.0 as u32 + color.1 as u32 + color.2 as u32) / 3;
+    let step = (mean.saturating_sub(3) / 10).min(23) as u8;
+    let grey_level = 8 + 10 * step;
+    let grey = (grey_level, grey_level, grey_level);
+
+    if distance(color, grey) < distance(color, cube) {
+        232 + step
+    } else {
+        cube_index as u8
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn detect() {
+        use ColorChoice::*;
+
+        let tty = |choice, no_color, colorterm, term| {
+            ColorDepth::from_env(choice, true, no_color, colorterm, term)
+        };
+        assert_eq!(
+            tty(Auto, None, Some("truecolor"), Some("xterm")),
+            ColorDepth::TrueColor
+        );
+        assert_eq!(tty(Auto, None, Some("24bit"), None), ColorDepth::TrueColor);
+        assert_eq!(
+            tty(Auto, None, None, Some("xterm-256color")),
+            ColorDepth::Ansi256
+        );
+        assert_eq!(tty(Auto, None, None, Some("xterm")), ColorDepth::Ansi16);
+        assert_eq!(tty(Auto, None, None, Some("dumb")), ColorDepth::None);
+        assert_eq!(
+            tty(Auto, Some("1"), Some("truecolor"), None),
+            ColorDepth::None
+        );
+        assert_eq!(tty(Auto, Some(""), None, Some("xterm")), ColorDepth::Ansi16);
+        assert_eq!(tty(Never, None, Some("truecolor"), None), ColorDepth::None);
+        assert_eq!(
+            tty(Always, Some("1"), Some("truecolor"), None),
+            ColorDepth::TrueColor
+        );
+
+        let pipe = |choice| ColorDepth::from_env(choice, false, None, Some("truecolor"), None);
+        assert_eq!(pipe(Auto), ColorDepth::None);
+        assert_eq!(pipe(Always), ColorDepth::TrueColor);
+    }
+
+    #[test]
+    fn nearest_colors() {
+        assert_eq!(nearest_ansi256((255, 0, 0)), 196);
+        assert_eq!(nearest_ansi256((0, 114, 178)), 25);
+        assert_eq!(nearest_ansi256((128, 128, 128)), 244);
+        assert_eq!(nearest_ansi256((0, 0, 0)), 16);
+        assert_eq!(nearest_ansi16((255, 255, 255)), 15);
+        assert_eq!(nearest_ansi16((0, 158, 115)), 6);
+        assert_eq!(nearest_ansi16((240, 228, 66)), 11);
+    }
+
+    #[test]
+    fn paint() {
+        let blue = (0, 114, 178);
+        assert_eq!(ColorDepth::None.paint("*", blue), "*");
+        assert_eq!(
+            ColorDepth::Ansi16.paint("*", (255, 0, 0)),
+            "\x1b[91m*\x1b[0m"
+        );
+        assert_eq!(
+            ColorDepth::Ansi16.paint("*", (205, 0, 0)),
+            "\x1b[31m*\x1b[0m"
+        );
+        assert_eq!(
+            ColorDepth::Ansi256.paint("*", blue),
+            "\x1b[38;5;25m*\x1b[0m"
+        );
+        assert_eq!(
+            ColorDepth::TrueColor.paint("*", blue),
+            "\x1b[38;2;0;114;178m*\x1b[0m"
+        );
+    }
+}
@@ -1,41 +1,277 @@
-use clap::Parser;
+use anyhow::Context;
+use clap::parser::ValueSource;
+use clap::{ArgAction, CommandFactory, FromArgMatches, Parser};
+use std::ffi::OsString;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
-#[derive(Parser, Debug, Default)]
+use crate::color::{ColorChoice, ColorDepth};
+use crate::config_file::{self, ConfigFile};
+use crate::theme::{ColorMap, Theme};
+
+#[derive(Parser, Debug, Clone)]
 #[clap(version, author, about)]
 pub struct Config {
     pub filename: Option<String>,
 
-    #[clap(long = "flip-xy")]
+    #[clap(long = "flip-xy", default_value = "false", num_args = 0..=1, require_equals = true, default_missing_value = "true", action = ArgAction::Set)]
     pub flip_xy: bool,
-    #[clap(short, long)]
+    #[clap(short, long, default_value = "false", num_args = 0..=1, require_equals = true, default_missing_value = "true", action = ArgAction::Set)]
     pub stream_mode: bool,
-    #[clap(long)]
+    #[clap(long, default_value = "false", num_args = 0..=1, require_equals = true, default_missing_value = "true", action = ArgAction::Set)]
     pub x_column: bool,
-
+    /// Field to read X values from, counting from 1. Overrides --x-column
+    #[clap(long, value_parser = parse_field_index)]
+    pub x_column_index: Option<usize>,
+    /// Read X values as timestamps: auto (ISO 8601 / RFC 3339), epoch, epoch-ms,
+    /// or a strftime pattern. Every line but comments must have one in the X
+    /// field, which is the first unless csv or tsv input picks another
     #[clap(long)]
+    pub x_time: Option<TimeFormat>,
+    /// Fields to plot, in order, by index (counting from 1) or header name
+    #[clap(long, value_delimiter = ',')]
+    pub columns: Vec<ColumnSelector>,
+    /// Read the first non-comment line as column names
+    #[clap(long, default_value = "false", num_args = 0..=1, require_equals = true, default_missing_value = "true", action = ArgAction::Set)]
+    pub header: bool,
+    /// How to split lines into fields: auto, csv or tsv
+    #[clap(long, default_value = "auto")]
+    pub format: InputFormat,
+
+    #[clap(long, default_value = "false", num_args = 0..=1, require_equals = true, default_missing_value = "true", action = ArgAction::Set)]
     pub log_x: bool,
-    #[clap(long)]
+    #[clap(long, default_value = "false", num_args = 0..=1, require_equals = true, default_missing_value = "true", action = ArgAction::Set)]
     pub log_y: bool,
-    #[clap(long)]
+    #[clap(long, default_value = "false", num_args = 0..=1, require_equals = true, default_missing_value = "true", action = ArgAction::Set)]
     pub log_count: bool,
 
-    #[clap(long, short, default_value = "80x40")]
-    pub dimensions: Dimensions,
+    /// Size of the plot, as <width>x<height>: characters for text output, and
+    /// pixels for SVG. Text drawn to a terminal fills it by default, and SVG
+    /// is 640x480
+    #[clap(long, short)]
+    pub dimensions: Option<Dimensions>,
 
-    #[clap(long, default_value = "true")]
+    #[clap(long, default_value = "true", num_args = 0..=1, require_equals = true, default_missing_value = "true", action = ArgAction::Set)]
     pub regression: bool,
 
+    /// How to draw each column: dot, line, count, bar, histogram, box, area or
+    /// stacked-area, or heatmap for the density of all the points
     #[clap(short, long, default_value = "dot")]
     pub mode: PlotType,
+    /// How bars for several columns share each X value in bar mode: grouped
+    /// side by side, or stacked on top of each other
+    #[clap(long, default_value = "grouped")]
+    pub bar_layout: BarLayout,
+    /// How line mode joins each point to the next: linear, step-before,
+    /// step-after or step-mid to change value at, after or midway between
+    /// them, or monotone or catmull-rom for smooth curves in SVG output
+    #[clap(long, default_value = "linear")]
+    pub interpolate: Interpolation,
+    /// Number of bins in histogram mode. Chosen from the spread of the values
+    /// when not given
+    #[clap(long, value_parser = parse_bins)]
+    pub bins: Option<usize>,
     #[clap(short, long = "output", default_value = "ascii")]
     pub output_type: OutputType,
+    /// Write the plot to a file instead of stdout, as svg, html or txt
+    /// depending on its extension, which --output must agree with. The file is
+    /// replaced in one step, and sized by --dimensions only when it is drawn in
+    /// the same units as --output
+    #[clap(long)]
+    pub output_file: Option<PathBuf>,
+    /// With --output-file, also draw the plot to stdout as --output
+    #[clap(long, requires = "output_file", default_value = "false", num_args = 0..=1, require_equals = true, default_missing_value = "true", action = ArgAction::Set)]
+    pub tee: bool,
 
-    #[clap(short = 'A', long, default_value = "true")]
+    #[clap(short = 'A', long, default_value = "true", num_args = 0..=1, require_equals = true, default_missing_value = "true", action = ArgAction::Set)]
     pub axis: bool,
+    /// Title for the X axis, in SVG output
+    #[clap(long)]
+    pub x_label: Option<String>,
+    /// Title for the Y axis, in SVG output
+    #[clap(long)]
+    pub y_label: Option<String>,
+    /// Where to put the key of column colours in SVG output: top-right, bottom,
+    /// outside or none
+    #[clap(long, default_value = "top-right")]
+    pub legend: LegendPosition,
+
+    /// Colour theme: bank-wong, mono-light or mono-dark, optionally followed by
+    /// :light or :dark for the background it will be seen on, or a theme from
+    /// the config file
+    #[clap(long, alias = "theme", default_value = "bank-wong")]
+    pub colors: String,
+    /// The theme named by --colors, once it has been looked up.
+    #[clap(skip)]
+    pub theme: Theme,
+    /// Colour scale for heatmap mode: viridis, magma or greys
+    #[clap(long, default_value = "viridis")]
+    pub colormap: ColorMap,
+    /// When to colour text output: auto (when drawing to a terminal, unless
+    /// NO_COLOR is set), always or never
+    #[clap(long, default_value = "auto")]
+    pub color: ColorChoice,
+    /// The colours text output can use, once --color and the terminal have been
+    /// checked. Without colour, no escape codes are written.
+    #[clap(skip)]
+    pub color_depth: ColorDepth,
+
+    /// Config file to read defaults and themes from, in place of
+    /// $XDG_CONFIG_HOME/plort/config.toml
+    #[clap(long = "config")]
+    pub config_path: Option<PathBuf>,
+    /// Use the settings from a [profile.NAME] section of the config file
+    #[clap(long)]
+    pub profile: Option<String>,
+}
+
+impl Default for Config {
+    /// The same defaults as the command line.
+    fn default() -> Self {
+        Config::parse_from(["plort"])
+    }
+}
+
+impl Config {
+    /// The size of the plot, falling back to the default for the output type.
+    pub fn dimensions_or_default(&self) -> Dimensions {
+        self.dimensions
+            .unwrap_or_else(|| Dimensions::default_for(self.output_type))
+    }
+
+    /// Parse the command line, filling in anything it leaves out from the config
+    /// file, and look up the theme. Exits on bad arguments, like `parse`.
+    pub fn load() -> anyhow::Result<Config> {
+        Self::load_from(std::env::args_os())
+    }
+
+    pub fn load_from<I, T>(args: I) -> anyhow::Result<Config>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString>,
+    {
+        let args: Vec<OsString> = args.into_iter().map(Into::into).collect();
+        let matches = Config::command().get_matches_from(&args);
+        let cli = Config::from_arg_matches(&matches)?;
+
+        // The default file is optional, but one asked for by name isn't.
+        let path = cli
+            .config_path
+            .clone()
+            .or_else(|| config_file::default_path().filter(|path| path.exists()));
+        let file = match &path {
+            Some(path) => ConfigFile::load(path)?,
+            None => ConfigFile::default(),
+        };
+
+        let mut config = match &path {
+            None if cli.profile.is_some() => {
+                anyhow::bail!("Profiles are read from a config file, and none was found")
+            }
+            Some(path) => {
+                let file_args = file
+                    .args(&matches, cli.profile.as_deref())
+                    .with_context(|| format!("In config file {}", path.display()))?;
+                let merged = args[..1]
+                    .iter()
+                    .cloned()
+                    .chain(file_args)
+                    .chain(args[1..].iter().cloned());
+                Config::try_parse_from(merged)
+                    .with_context(|| format!("In config file {}", path.display()))?
+            }
+            None => cli,
+        };
+        config.theme = file.theme(&config.colors)?;
+        config.color_depth = ColorDepth::detect(config.color, std::io::stdout().is_terminal());
+
+        // The file's extension picks its output type, so --output can only agree.
+        if let Some(path) = &config.output_file {
+            let output_type =
+                OutputType::for_path(path, config.output_type).map_err(anyhow::Error::msg)?;
+            if matches.value_source("output_type") == Some(ValueSource::CommandLine)
+                && output_type != config.output_type
+            {
+                anyhow::bail!("--output doesn't match the extension of {}", path.display());
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// The input field holding X values, counting from 0.
+    pub fn x_field(&self) -> Option<usize> {
+        match self.x_column_index {
+            Some(index) => Some(index - 1),
+            None if self.x_column || self.x_time.is_some() => Some(0),
+            None => None,
+        }
+    }
+}
+
+fn parse_field_index(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(0) => Err("Field indices count from 1".to_string()),
+        Ok(index) => Ok(index),
+        Err(e) => Err(format!("Invalid field index: {}", e)),
+    }
+}
+
+fn parse_bins(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(0) => Err("A histogram needs at least one bin".to_string()),
+        Ok(bins) => Ok(bins),
+        Err(e) => Err(format!("Invalid number of bins: {}", e)),
+    }
+}
+
+/// An input field, either by index (counting from 1) or by header name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColumnSelector {
+    Index(usize),
+    Name(String),
+}
+
+impl FromStr for ColumnSelector {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.chars().all(|c| c.is_ascii_digit()) {
+            parse_field_index(s).map(ColumnSelector::Index)
+        } else if s.is_empty() {
+            Err("Empty column name".to_string())
+        } else {
+            Ok(ColumnSelector::Name(s.to_string()))
+        }
+    }
+}
 
-    #[clap(long = "colors", default_value = "bank-wong")]
-    pub color_scheme: ColorScheme,
+/// How timestamps in the X field are written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimeFormat {
+    /// ISO 8601 or RFC 3339.
+    Auto,
+    /// Seconds since the Unix epoch.
+    Epoch,
+    /// Milliseconds since the Unix epoch.
+    EpochMillis,
+    /// A strftime-style pattern.
+    Pattern(String),
+}
+
+impl FromStr for TimeFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(TimeFormat::Auto),
+            "epoch" => Ok(TimeFormat::Epoch),
+            "epoch-ms" => Ok(TimeFormat::EpochMillis),
+            _ if s.contains('%') => Ok(TimeFormat::Pattern(s.to_string())),
+            _ => Err(format!("Unknown time format: {}", s)),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -44,11 +280,20 @@ pub struct Dimensions {
     pub height: usize,
 }
 
-impl Default for Dimensions {
-    fn default() -> Self {
-        Dimensions {
-            width: 72,
-            height: 40,
+impl Dimensions {
+    /// The size used when none is given: characters for text output, and
+    /// pixels for SVG.
+    pub fn default_for(output_type: OutputType) -> Self {
+        if output_type.is_text() {
+            Dimensions {
+                width: 80,
+                height: 40,
+            }
+        } else {
+            Dimensions {
+                width: 640,
+                height: 480,
+            }
         }
     }
 }
@@ -81,6 +326,31 @@ pub enum PlotType {
     Dot,
     Line,
     Count,
+    /// Bars from the X axis to each value.
+    Bar,
+    /// Bars counting the values of each column which fall in each bin.
+    Histogram,
+    /// A box and whiskers summarising the values of each column, side by side.
+    Box,
+    /// Cells shaded by how many points of any column fall in them.
+    Heatmap,
+    /// Each column filled down to the X axis.
+    Area,
+    /// The columns filled one on top of another, each from the total of those
+    /// before it.
+    StackedArea,
+}
+
+impl PlotType {
+    /// Whether the plot is drawn as bars.
+    pub fn is_bars(self) -> bool {
+        matches!(self, PlotType::Bar | PlotType::Histogram)
+    }
+
+    /// Whether the plot is drawn as filled areas.
+    pub fn is_areas(self) -> bool {
+        matches!(self, PlotType::Area | PlotType::StackedArea)
+    }
 }
 
 impl FromStr for PlotType {
@@ -91,17 +361,153 @@ impl FromStr for PlotType {
             "dot" => Ok(PlotType::Dot),
             "line" => Ok(PlotType::Line),
             "count" => Ok(PlotType::Count),
+            "bar" => Ok(PlotType::Bar),
+            "histogram" => Ok(PlotType::Histogram),
+            "box" => Ok(PlotType::Box),
+            "heatmap" => Ok(PlotType::Heatmap),
+            "area" => Ok(PlotType::Area),
+            "stacked-area" => Ok(PlotType::StackedArea),
             _ => Err(format!("Unknown plot type: {}", s)),
         }
     }
 }
 
+/// How the bars of several columns are laid out in bar mode.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BarLayout {
+    /// Side by side, each column taking its share of the space for an X value.
+    #[default]
+    Grouped,
+    /// One on top of another, positive values above the axis and negative
+    /// values below.
+    Stacked,
+}
+
+/// How the points of a column are joined up in line mode.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Interpolation {
+    /// Straight from each point to the next.
+    #[default]
+    Linear,
+    /// Steps up or down at the X value of each point, as though it held from
+    /// just after the point before it.
+    StepBefore,
+    /// Steps up or down at the X value of the next point, as though each point
+    /// held until then.
+    StepAfter,
+    /// Steps up or down halfway between each point and the next.
+    StepMid,
+    /// A smooth curve which never goes past the values either side of it, so
+    /// adds no peaks or troughs of its own. Straight lines in text output.
+    Monotone,
+    /// A smooth Catmull-Rom spline through the points. Straight lines in text
+    /// output.
+    CatmullRom,
+}
+
+impl Interpolation {
+    /// Whether the points are joined up with curves rather than straight lines.
+    pub fn is_smooth(self) -> bool {
+        matches!(self, Interpolation::Monotone | Interpolation::CatmullRom)
+    }
+}
+
+impl FromStr for Interpolation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "linear" => Ok(Interpolation::Linear),
+            "step-before" => Ok(Interpolation::StepBefore),
+            "step-after" => Ok(Interpolation::StepAfter),
+            "step-mid" => Ok(Interpolation::StepMid),
+            "monotone" => Ok(Interpolation::Monotone),
+            "catmull-rom" => Ok(Interpolation::CatmullRom),
+            _ => Err(format!("Unknown interpolation: {}", s)),
+        }
+    }
+}
+
+impl FromStr for BarLayout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "grouped" => Ok(BarLayout::Grouped),
+            "stacked" => Ok(BarLayout::Stacked),
+            _ => Err(format!("Unknown bar layout: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum InputFormat {
+    /// Take every run of number-like characters as a value.
+    #[default]
+    Auto,
+    Csv,
+    Tsv,
+}
+
+impl InputFormat {
+    /// The field delimiter, for formats which have one.
+    pub fn delimiter(&self) -> Option<char> {
+        match self {
+            InputFormat::Auto => None,
+            InputFormat::Csv => Some(','),
+            InputFormat::Tsv => Some('\t'),
+        }
+    }
+}
+
+impl FromStr for InputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(InputFormat::Auto),
+            "csv" => Ok(InputFormat::Csv),
+            "tsv" => Ok(InputFormat::Tsv),
+            _ => Err(format!("Unknown input format: {}", s)),
+        }
+    }
+}
+
 #[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
 pub enum OutputType {
     #[default]
     Ascii,
     Braille,
     Svg,
+    /// An SVG in a standalone web page.
+    Html,
+}
+
+impl OutputType {
+    /// The output type for a file, from its extension. Text files take the
+    /// text renderer asked for, or ascii.
+    pub fn for_path(path: &Path, requested: OutputType) -> Result<Self, String> {
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("svg") => Ok(OutputType::Svg),
+            Some("html") | Some("htm") => Ok(OutputType::Html),
+            Some("txt") if requested.is_text() => Ok(requested),
+            Some("txt") => Ok(OutputType::Ascii),
+            Some("png") => Err("PNG output isn't supported yet".to_string()),
+            _ => Err(format!(
+                "Can't tell the output type of {} from its extension",
+                path.display()
+            )),
+        }
+    }
+
+    /// Whether the output is text to be read in a terminal.
+    pub fn is_text(self) -> bool {
+        matches!(self, OutputType::Ascii | OutputType::Braille)
+    }
 }
 
 impl FromStr for OutputType {
@@ -110,75 +516,71 @@ impl FromStr for OutputType {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "ascii" => Ok(OutputType::Ascii),
-            "braile" => Ok(OutputType::Braille),
+            "braille" | "braile" => Ok(OutputType::Braille),
             "svg" => Ok(OutputType::Svg),
+            "html" => Ok(OutputType::Html),
             _ => Err(format!("Unknown output type: {}", s)),
         }
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct ColorScheme {
-    axis: (u8, u8, u8),
-    series: &'static [(u8, u8, u8)],
-}
-
-impl ColorScheme {
-    pub fn series_color(&self, col: usize) -> (u8, u8, u8) {
-        self.series[col % self.series.len()]
-    }
-
-    pub fn axis_color(&self) -> (u8, u8, u8) {
-        self.axis
-    }
+/// Where the legend goes, relative to the plot area.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum LegendPosition {
+    /// Inside the plot area, in its top right corner.
+    #[default]
+    TopRight,
+    /// In a row below the plot area.
+    Bottom,
+    /// To the right of the plot area.
+    Outside,
+    None,
 }
 
-impl FromStr for ColorScheme {
+impl FromStr for LegendPosition {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "bank-wong" => Ok(BANK_WONG_COLOR_SCHEME),
-            "mono-light" => Ok(MONO_LIGHT_SCHEME),
-            "mono-dark" => Ok(MONO_DARK_SCHEME),
-
-            _ => Err(format!("Unknown color scheme: {}", s)),
+            "top-right" => Ok(LegendPosition::TopRight),
+            "bottom" => Ok(LegendPosition::Bottom),
+            "outside" => Ok(LegendPosition::Outside),
+            "none" => Ok(LegendPosition::None),
+            _ => Err(format!("Unknown legend position: {}", s)),
         }
     }
 }
 
-impl Default for ColorScheme {
-    fn default() -> Self {
-        BANK_WONG_COLOR_SCHEME
-    }
-}
+#[cfg(test)]
+mod test {
+    use super::*;
 
-pub const BANK_WONG_COLOR_SCHEME: ColorScheme = {
-    ColorScheme {
-        axis: (123, 123, 125),
-        series: &[
-            (0, 114, 178),
-            (230, 159, 0),
-            (86, 180, 233),
-            (0, 158, 115),
-            (240, 228, 66),
-            (0, 0, 0),
-            (213, 94, 0),
-            (204, 121, 167),
-        ],
+    #[test]
+    fn output_type_for_path() {
+        let for_path = |path: &str, requested| OutputType::for_path(Path::new(path), requested);
+        assert_eq!(for_path("a.svg", OutputType::Ascii), Ok(OutputType::Svg));
+        assert_eq!(
+            for_path("a/b.HTML", OutputType::Ascii),
+            Ok(OutputType::Html)
+        );
+        assert_eq!(
+            for_path("a.txt", OutputType::Braille),
+            Ok(OutputType::Braille)
+        );
+        assert_eq!(for_path("a.txt", OutputType::Svg), Ok(OutputType::Ascii));
+        assert!(for_path("a.png", OutputType::Ascii).is_err());
+        assert!(for_path("a", OutputType::Ascii).is_err());
     }
-};
 
-pub const MONO_LIGHT_SCHEME: ColorScheme = {
-    ColorScheme {
-        axis: (255, 255, 255),
-        series: &[(255, 255, 255)],
-    }
-};
+    #[test]
+    fn default_dimensions() {
+        let config = Config::parse_from(["plort"]);
+        assert_eq!(config.dimensions_or_default(), "80x40".parse().unwrap());
+
+        let config = Config::parse_from(["plort", "-o", "svg"]);
+        assert_eq!(config.dimensions_or_default(), "640x480".parse().unwrap());
 
-pub const MONO_DARK_SCHEME: ColorScheme = {
-    ColorScheme {
-        axis: (0, 0, 0),
-        series: &[(0, 0, 0)],
+        let config = Config::parse_from(["plort", "-o", "svg", "-d", "300x200"]);
+        assert_eq!(config.dimensions_or_default(), "300x200".parse().unwrap());
     }
-};
+}
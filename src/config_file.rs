@@ -0,0 +1,325 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory};
+use serde::Deserialize;
+
+use crate::config::Config;
+use crate::theme::{parse_hex, Theme};
+
+/// Defaults for the command line, custom themes, and profiles which bundle
+/// settings under a name, read from a TOML file. Settings use the long names of
+/// the command line flags:
+///
+/// ```toml
+/// colors = "solarized"
+/// dimensions = "100x30"
+/// regression = false
+///
+/// [themes.solarized]
+/// base = "bank-wong:dark"
+/// background = "#002b36"
+/// series = ["#268bd2", "#dc322f", "#859900"]
+///
+/// [profile.latency]
+/// mode = "line"
+/// log-y = true
+/// columns = ["p50", "p99"]
+/// ```
+#[derive(Debug, Default, Deserialize)]
+pub struct ConfigFile {
+    #[serde(default)]
+    themes: HashMap<String, ThemeSpec>,
+    #[serde(default, rename = "profile")]
+    profiles: HashMap<String, toml::Table>,
+    #[serde(flatten)]
+    settings: toml::Table,
+}
+
+/// Changes to a built in theme.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+struct ThemeSpec {
+    /// The built in theme to start from. Defaults to bank-wong.
+    base: Option<String>,
+    background: Option<String>,
+    foreground: Option<String>,
+    axis: Option<String>,
+    grid: Option<String>,
+    series: Option<Vec<String>>,
+    line_width: Option<f64>,
+    axis_width: Option<f64>,
+    border_width: Option<f64>,
+    marker: Option<String>,
+    marker_size: Option<f64>,
+}
+
+/// `$XDG_CONFIG_HOME/plort/config.toml`, or under `~/.config` when that isn't
+/// set.
+pub fn default_path() -> Option<PathBuf> {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(config_home.join("plort").join("config.toml"))
+}
+
+impl ConfigFile {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Reading config file {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("Parsing config file {}", path.display()))
+    }
+
+    fn parse(text: &str) -> anyhow::Result<Self> {
+        Ok(toml::from_str(text)?)
+    }
+
+    /// The settings as command line arguments, with those of the profile taking
+    /// the place of the defaults. Settings given on the command line itself are
+    /// left out.
+    pub fn args(
+        &self,
+        matches: &ArgMatches,
+        profile: Option<&str>,
+    ) -> anyhow::Result<Vec<OsString>> {
+        let mut settings = self.settings.clone();
+        if let Some(name) = profile {
+            let profile = self
+                .profiles
+                .get(name)
+                .with_context(|| format!("Unknown profile: {}", name))?;
+            settings.extend(profile.clone());
+        }
+
+        let command = Config::command();
+        let mut args = Vec::new();
+        for (key, value) in &settings {
+            let arg = command
+                .get_arguments()
+                .find(|arg| {
+                    arg.get_long() == Some(key.as_str())
+                        || arg
+                            .get_all_aliases()
+                            .is_some_and(|aliases| aliases.contains(&key.as_str()))
+                })
+                .filter(|arg| !["config_path", "profile"].contains(&arg.get_id().as_str()))
+                .with_context(|| format!("Unknown setting: {}", key))?;
+
+            if matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine) {
+                continue;
+            }
+
+            // Switches take an optional true or false, so the command line can
+            // turn off one the file turns on.
+            let switch = arg
+                .get_possible_values()
+                .iter()
+                .any(|possible| possible.get_name() == "true");
+            if switch && !value.is_bool() {
+                anyhow::bail!("{} should be true or false", key);
+            }
+            args.push(format!("--{}={}", key, setting_value(key, value)?).into());
+        }
+
+        Ok(args)
+    }
+
+    /// A theme by name, either defined in the file or built in.
+    pub fn theme(&self, name: &str) -> anyhow::Result<Theme> {
+        let Some(spec) = self.themes.get(name) else {
+            return name.parse().map_err(anyhow::Error::msg);
+        };
+
+        let base = spec.base.as_deref().unwrap_or("bank-wong");
+        let mut theme: Theme = base
+            .parse()
+            .map_err(anyhow::Error::msg)
+            .with_context(|| format!("In theme {}", name))?;
+        spec.apply(&mut theme)
+            .map_err(anyhow::Error::msg)
+            .with_context(|| format!("In theme {}", name))?;
+
+        Ok(theme)
+    }
+}
+
+impl ThemeSpec {
+    fn apply(&self, theme: &mut Theme) -> Result<(), String> {
+        let colors = [
+            (&self.background, &mut theme.background),
+            (&self.foreground, &mut theme.foreground),
+            (&self.axis, &mut theme.axis),
+            (&self.grid, &mut theme.grid),
+        ];
+        for (color, field) in colors {
+            if let Some(color) = color {
+                *field = parse_hex(color)?;
+            }
+        }
+
+        if let Some(series) = &self.series {
+            if series.is_empty() {
+                return Err("series needs at least one colour".to_string());
+            }
+            theme.series = series
+                .iter()
+                .map(|color| parse_hex(color))
+                .collect::<Result<_, _>>()?;
+        }
+
+        let widths = [
+            (self.line_width, &mut theme.line_width),
+            (self.axis_width, &mut theme.axis_width),
+            (self.border_width, &mut theme.border_width),
+            (self.marker_size, &mut theme.marker_size),
+        ];
+        for (width, field) in widths {
+            if let Some(width) = width {
+                *field = width;
+            }
+        }
+
+        if let Some(marker) = &self.marker {
+            theme.marker = marker.parse()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A setting's value as it would be written on the command line.
+fn setting_value(key: &str, value: &toml::Value) -> anyhow::Result<String> {
+    match value {
+        toml::Value::String(s) => Ok(s.clone()),
+        toml::Value::Integer(i) => Ok(i.to_string()),
+        toml::Value::Float(f) => Ok(f.to_string()),
+        toml::Value::Boolean(b) => Ok(b.to_string()),
+        toml::Value::Array(values) => Ok(values
+            .iter()
+            .map(|value| setting_value(key, value))
+            .collect::<anyhow::Result<Vec<_>>>()?
+            .join(",")),
+        _ => anyhow::bail!("Unsupported value for {}", key),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(file: &str, cli: &[&str]) -> anyhow::Result<Vec<OsString>> {
+        let matches = Config::command().get_matches_from(cli);
+        let profile = matches.get_one::<String>("profile").map(String::as_str);
+        ConfigFile::parse(file)?.args(&matches, profile)
+    }
+
+    #[test]
+    fn settings_become_args() {
+        let file = r#"
+            dimensions = "100x30"
+            regression = false
+            log-y = true
+            header = false
+            columns = [2, "mem"]
+        "#;
+        let args = args(file, &["plort"]).unwrap();
+        assert_eq!(
+            args,
+            [
+                "--columns=2,mem",
+                "--dimensions=100x30",
+                "--header=false",
+                "--log-y=true",
+                "--regression=false"
+            ]
+        );
+    }
+
+    #[test]
+    fn command_line_wins() {
+        let file = r#"
+            dimensions = "100x30"
+            mode = "line"
+        "#;
+        let args = args(file, &["plort", "-d", "20x10"]).unwrap();
+        assert_eq!(args, ["--mode=line"]);
+    }
+
+    #[test]
+    fn profiles() {
+        let file = r#"
+            dimensions = "100x30"
+            mode = "dot"
+
+            [profile.latency]
+            mode = "line"
+            log-y = true
+            columns = ["p50", "p99"]
+        "#;
+        let latency = args(file, &["plort", "--profile", "latency", "--log-y"]).unwrap();
+        assert_eq!(
+            latency,
+            ["--columns=p50,p99", "--dimensions=100x30", "--mode=line"]
+        );
+
+        let off = args(file, &["plort", "--profile", "latency", "--log-y=false"]).unwrap();
+        assert!(!off.contains(&"--log-y=true".into()));
+
+        let defaults = args(file, &["plort"]).unwrap();
+        assert_eq!(defaults, ["--dimensions=100x30", "--mode=dot"]);
+
+        assert!(args(file, &["plort", "--profile", "cpu"]).is_err());
+        assert!(args("profile = \"latency\"", &["plort"]).is_err());
+    }
+
+    #[test]
+    fn aliases() {
+        let args = args("theme = \"mono-dark\"", &["plort"]).unwrap();
+        assert_eq!(args, ["--theme=mono-dark"]);
+    }
+
+    #[test]
+    fn unknown_setting() {
+        assert!(args("colours = \"mono-dark\"", &["plort"]).is_err());
+        assert!(args("config = \"other.toml\"", &["plort"]).is_err());
+        assert!(args("log-x = \"yes\"", &["plort"]).is_err());
+    }
+
+    #[test]
+    fn custom_theme() {
+        let file = ConfigFile::parse(
+            r##"
+            [themes.paper]
+            base = "bank-wong:light"
+            background = "#fdf6e3"
+            series = ["#268bd2", "#d33"]
+            marker = "square"
+        "##,
+        )
+        .unwrap();
+
+        let theme = file.theme("paper").unwrap();
+        assert_eq!(theme.background, (0xfd, 0xf6, 0xe3));
+        assert_eq!(theme.foreground, (0, 0, 0));
+        assert_eq!(theme.series, [(0x26, 0x8b, 0xd2), (0xdd, 0x33, 0x33)]);
+        assert_eq!(theme.marker, crate::theme::Marker::Square);
+
+        assert_eq!(
+            file.theme("mono-dark").unwrap(),
+            Theme::builtin("mono-dark", None).unwrap()
+        );
+        assert!(file.theme("solarized").is_err());
+    }
+
+    #[test]
+    fn bad_theme() {
+        let file = ConfigFile::parse("[themes.bad]\nbackground = \"blue\"").unwrap();
+        assert!(file.theme("bad").is_err());
+
+        assert!(ConfigFile::parse("[themes.bad]\nbackgroud = \"#000\"").is_err());
+    }
+}
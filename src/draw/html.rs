@@ -0,0 +1,22 @@
+use std::io::Write;
+
+use crate::draw::{svg::SvgPlot, Plot, Renderer};
+
+/// Renders a plot as an SVG inside a standalone HTML page.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct HtmlPlot;
+
+impl Renderer for HtmlPlot {
+    fn render(&self, plot: &Plot, out: &mut dyn Write) -> std::io::Result<()> {
+        writeln!(out, "<!DOCTYPE html>")?;
+        writeln!(out, r#"<html lang="en">"#)?;
+        writeln!(
+            out,
+            r#"<head><meta charset="utf-8"><title>plort</title></head>"#
+        )?;
+        writeln!(out, "<body>")?;
+        SvgPlot.render(plot, out)?;
+        writeln!(out, "</body>")?;
+        writeln!(out, "</html>")
+    }
+}
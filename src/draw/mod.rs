@@ -1,22 +1,42 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::io::Write;
 
-use crate::config::{Config, OutputType};
-use crate::scale::ScaledPoint;
+use crate::config::{
+    BarLayout, Config, Dimensions, Interpolation, LegendPosition, OutputType, PlotType,
+};
+use crate::histogram::histogram;
 use crate::scale::TransformType;
+use crate::scale::{Bounds, ExactPoint, ScaledPoint};
+use crate::stats::BoxStats;
+use crate::theme::Theme;
+use crate::ticks::{time_ticks, value_ticks, Tick};
 use crate::types::DataSet;
 use crate::types::Point;
-use svg::{svg_plot, SvgTheme};
-use text::ascii::ascii_plot;
-use text::braille::braille_plot;
+use html::HtmlPlot;
+use svg::SvgPlot;
+use text::ascii::AsciiPlot;
+use text::braille::BraillePlot;
 
-mod svg;
-mod text;
+pub mod html;
+pub mod svg;
+pub mod text;
 
 const CROSS_PAD: f64 = 2.0;
+/// The share of the space between X values taken up by bars, leaving a gap
+/// between neighbours.
+const BAR_FILL: f64 = 0.8;
 
+/// A backend which draws a plot to a writer.
+pub trait Renderer {
+    fn render(&self, plot: &Plot, out: &mut dyn Write) -> std::io::Result<()>;
+}
+
+/// A dataset along with the configuration and bounds needed to render it.
 pub struct Plot<'a> {
-    dataset: &'a DataSet,
-    config: &'a Config,
+    /// The dataset as given, or the bins counted from it in histogram mode.
+    dataset: Cow<'a, DataSet>,
+    config: Config,
     x_min: f64,
     x_max: f64,
     y_min: f64,
@@ -24,7 +44,20 @@ pub struct Plot<'a> {
 }
 
 impl<'a> Plot<'a> {
-    pub fn new(dataset: &'a DataSet, config: &'a Config) -> anyhow::Result<Self> {
+    pub fn new(dataset: &'a DataSet, config: &Config) -> anyhow::Result<Self> {
+        if config.log_x && time_x(config) {
+            anyhow::bail!("Log scale can't be used for timestamps");
+        }
+        if config.log_x && config.mode == PlotType::Box {
+            anyhow::bail!("Box plots have no X scale to take the log of");
+        }
+
+        let dataset = if config.mode == PlotType::Histogram {
+            Cow::Owned(histogram(dataset, config)?)
+        } else {
+            Cow::Borrowed(dataset)
+        };
+
         let mut min_point = Point(f64::MAX, f64::MAX);
         let mut max_point = Point(f64::MIN, f64::MIN);
 
@@ -62,14 +95,40 @@ impl<'a> Plot<'a> {
             }
         }
 
+        let stacked_bars = config.mode.is_bars() && config.bar_layout == BarLayout::Stacked;
+        if stacked_bars || config.mode == PlotType::StackedArea {
+            if config.log_y {
+                let what = if stacked_bars { "bars" } else { "areas" };
+                anyhow::bail!("Stacked {} can't be drawn on a log scale", what);
+            }
+            for row in 0..dataset.rows {
+                let (below, above) = stack_heights(&dataset, row);
+                min_point.1 = min_point.1.min(below);
+                max_point.1 = max_point.1.max(above);
+            }
+        }
+
         let transform = TransformType::new(config.log_x, config.log_y);
-        let min_point = min_point.scale_transform(transform);
+        let mut min_point = min_point.scale_transform(transform);
         let mut max_point = max_point.scale_transform(transform);
 
-        if min_point.0 == max_point.0 {
+        // Bars get room either side of their X value below instead.
+        if min_point.0 == max_point.0 && !config.mode.is_bars() {
             max_point.0 += 1.0;
         }
 
+        // Counts start from nothing.
+        if config.mode == PlotType::Histogram && !config.log_y {
+            min_point.1 = min_point.1.min(0.0);
+        }
+
+        // Leave room for the bars either side of the first and last X values.
+        if config.mode.is_bars() {
+            let half_slot = bar_slot(&dataset, transform) / 2.0;
+            min_point.0 -= half_slot;
+            max_point.0 += half_slot;
+        }
+
         if min_point.1 == max_point.1 {
             max_point.1 += 1.0;
         }
@@ -86,8 +145,9 @@ impl<'a> Plot<'a> {
         let crosses_y_axis = y_min <= 0.0 && y_max >= 0.0;
 
         // If the data does not cross the x or y axis, we can
-        // clamp the plot's axis to zero.
-        if !crosses_x_axis {
+        // clamp the plot's axis to zero. Timestamps are never clamped
+        // to the epoch.
+        if !crosses_x_axis && !time_x(config) {
             if 0.0 < x_min && 0.0 > x_min - x_range * CROSS_PAD {
                 x_min = 0.0;
             } else if 0.0 > x_max && 0.0 < x_max + x_range * CROSS_PAD {
@@ -95,7 +155,8 @@ impl<'a> Plot<'a> {
             }
         }
 
-        if !crosses_y_axis {
+        // Box plots are about the spread of the values, so are kept to it.
+        if !crosses_y_axis && config.mode != PlotType::Box {
             if 0.0 < y_min && 0.0 > y_min - y_range * CROSS_PAD {
                 y_min = 0.0;
             } else if 0.0 > y_max && 0.0 < y_max + y_range * CROSS_PAD {
@@ -107,13 +168,19 @@ impl<'a> Plot<'a> {
             anyhow::bail!("No data to plot");
         }
 
+        // Each column gets a box, one unit apart.
+        if config.mode == PlotType::Box {
+            x_min = -0.5;
+            x_max = dataset.columns as f64 - 0.5;
+        }
+
         if x_min == x_max || y_min == y_max {
             anyhow::bail!("Insufficient range of data");
         }
 
         Ok(Self {
             dataset,
-            config,
+            config: config.clone(),
             x_min,
             x_max,
             y_min,
@@ -121,6 +188,23 @@ impl<'a> Plot<'a> {
         })
     }
 
+    /// Start building a plot of the given dataset, with the same defaults as
+    /// the command line.
+    pub fn builder(dataset: &'a DataSet) -> PlotBuilder<'a> {
+        PlotBuilder {
+            dataset,
+            config: Config::default(),
+        }
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn dataset(&self) -> &DataSet {
+        &self.dataset
+    }
+
     pub fn x_min(&self) -> f64 {
         self.x_min
     }
@@ -138,11 +222,11 @@ impl<'a> Plot<'a> {
     }
 
     pub fn height(&self) -> usize {
-        self.config.dimensions.height
+        self.config.dimensions_or_default().height
     }
 
     pub fn width(&self) -> usize {
-        self.config.dimensions.width
+        self.config.dimensions_or_default().width
     }
 
     pub fn log_x(&self) -> bool {
@@ -153,6 +237,20 @@ impl<'a> Plot<'a> {
         self.config.log_y
     }
 
+    /// Whether X values are timestamps, in seconds since the Unix epoch.
+    pub fn time_x(&self) -> bool {
+        time_x(&self.config)
+    }
+
+    /// Format an X value for display.
+    pub fn format_x(&self, x: f64) -> String {
+        if self.time_x() {
+            crate::time::format_time(x)
+        } else {
+            x.to_string()
+        }
+    }
+
     pub fn x_range(&self) -> f64 {
         self.x_max - self.x_min
     }
@@ -166,13 +264,20 @@ impl<'a> Plot<'a> {
     }
 
     fn draw_y_axis(&self) -> bool {
-        0.0 >= self.x_min && 0.0 <= self.x_max
+        // Box plots have no X scale, so the Y axis goes down the left edge.
+        self.config.mode != PlotType::Box && 0.0 >= self.x_min && 0.0 <= self.x_max
     }
 
     pub fn axis_positions(&self) -> (usize, usize) {
+        self.scaled_axis_positions(self.width(), self.height())
+    }
+
+    /// Axis positions on a grid of the given size, for renderers whose
+    /// resolution differs from the plot's character dimensions.
+    pub fn scaled_axis_positions(&self, width: usize, height: usize) -> (usize, usize) {
         let mut origin = Point(0.0, 0.0);
         if !self.draw_y_axis() {
-            if 0.0 < self.x_min {
+            if 0.0 < self.x_min || self.config.mode == PlotType::Box {
                 origin.0 = self.x_min;
             } else {
                 origin.0 = self.x_max;
@@ -187,16 +292,200 @@ impl<'a> Plot<'a> {
             }
         }
 
-        let sp = ScaledPoint::new_from_plot(
+        let sp = ScaledPoint::new_from_bounds(
             origin,
-            self,
             TransformType::new(self.config.log_x, self.config.log_y),
+            self.bounds(width, height),
         );
         // XXX Is this safe? SP are signed, but x_axis and y_axis are unsigned.
         (sp.0 as usize, sp.1 as usize)
     }
 
-    pub fn counters(&self) -> Counters {
+    /// Ticks for the X axis, at most `max_ticks` of them for a scale. Box plots
+    /// have a tick for each column, labelled with its name.
+    pub(crate) fn x_tick_values(&self, max_ticks: usize) -> Vec<Tick> {
+        if self.config.mode == PlotType::Box {
+            (0..self.dataset.columns)
+                .map(|col| Tick {
+                    value: col as f64,
+                    label: self.dataset.column_label(col),
+                })
+                .collect()
+        } else if self.time_x() {
+            time_ticks(self.x_min, self.x_max, max_ticks)
+        } else {
+            value_ticks(self.x_min, self.x_max, self.log_x(), max_ticks)
+        }
+    }
+
+    /// The plot's data bounds, scaled onto a grid of the given size.
+    pub(crate) fn bounds(&self, width: usize, height: usize) -> Bounds {
+        Bounds {
+            x_min: self.x_min,
+            x_max: self.x_max,
+            y_min: self.y_min,
+            y_max: self.y_max,
+            width,
+            height,
+        }
+    }
+
+    /// The Y value bars start from: zero, or the edge of the plot nearest it
+    /// when zero is out of range. In log scaled coordinates, like the bounds.
+    pub fn baseline(&self) -> f64 {
+        if self.draw_x_axis() {
+            0.0
+        } else if 0.0 < self.y_min {
+            self.y_min
+        } else {
+            self.y_max
+        }
+    }
+
+    /// The bars of bar mode, laid out according to the bar layout.
+    pub fn bars(&self) -> Vec<Bar> {
+        let transform = TransformType::new(self.log_x(), self.log_y());
+        // The bins of a histogram meet.
+        let fill = if self.config.mode == PlotType::Histogram {
+            1.0
+        } else {
+            BAR_FILL
+        };
+        let slot = bar_slot(&self.dataset, transform) * fill;
+        let stacked = self.config.bar_layout == BarLayout::Stacked;
+        let width = if stacked {
+            slot
+        } else {
+            slot / self.dataset.columns.max(1) as f64
+        };
+
+        let mut bars = Vec::new();
+        for row in 0..self.dataset.rows {
+            let (mut below, mut above) = (0.0, 0.0);
+            for col in 0..self.dataset.columns {
+                let point = self.dataset.points[col][row];
+                if point.is_empty() {
+                    continue;
+                }
+
+                let Point(x, y) = point.scale_transform(transform);
+                let left = x - slot / 2.0;
+                let bar = if stacked {
+                    let stack = if y < 0.0 { &mut below } else { &mut above };
+                    let base = *stack;
+                    *stack += y;
+                    Bar {
+                        column: col,
+                        left,
+                        right: left + width,
+                        base,
+                        value: *stack,
+                    }
+                } else {
+                    let left = left + width * col as f64;
+                    Bar {
+                        column: col,
+                        left,
+                        right: left + width,
+                        base: self.baseline(),
+                        value: y,
+                    }
+                };
+                // Keep float error in the widths from taking bars off the plot.
+                bars.push(Bar {
+                    left: bar.left.max(self.x_min),
+                    right: bar.right.min(self.x_max),
+                    ..bar
+                });
+            }
+        }
+
+        bars
+    }
+
+    /// The runs of consecutive values of each column in area mode, broken by
+    /// empty values like the lines of line mode. In stacked area mode each
+    /// value is filled from the total of those before it in its row, with
+    /// negative values stacked downwards as in stacked bars.
+    pub fn areas(&self) -> Vec<Area> {
+        let transform = TransformType::new(self.log_x(), self.log_y());
+        let stacked = self.config.mode == PlotType::StackedArea;
+        let columns = self.dataset.columns;
+
+        let mut areas = Vec::new();
+        let mut runs: Vec<Vec<AreaPoint>> = vec![Vec::new(); columns];
+        for row in 0..self.dataset.rows {
+            let (mut below, mut above) = (0.0, 0.0);
+            for (col, run) in runs.iter_mut().enumerate() {
+                let point = self.dataset.points[col][row];
+                if point.is_empty() {
+                    if !run.is_empty() {
+                        areas.push(Area {
+                            column: col,
+                            points: std::mem::take(run),
+                        });
+                    }
+                    continue;
+                }
+
+                let Point(x, y) = point.scale_transform(transform);
+                // A column changing sign moves to the other stack, so its area
+                // ends there rather than cutting across the columns between.
+                let last_below = run.last().map(|last| last.value < last.base);
+                if stacked && last_below.is_some_and(|last_below| last_below != (y < 0.0)) {
+                    areas.push(Area {
+                        column: col,
+                        points: std::mem::take(run),
+                    });
+                }
+
+                let (base, value) = if stacked {
+                    let stack = if y < 0.0 { &mut below } else { &mut above };
+                    let base = *stack;
+                    *stack += y;
+                    (base, *stack)
+                } else {
+                    (self.baseline(), y)
+                };
+                run.push(AreaPoint { x, base, value });
+            }
+        }
+        for (col, run) in runs.into_iter().enumerate() {
+            if !run.is_empty() {
+                areas.push(Area {
+                    column: col,
+                    points: run,
+                });
+            }
+        }
+
+        // Draw the columns in order, so that later ones sit on top.
+        areas.sort_by_key(|area| area.column);
+        areas
+    }
+
+    /// The summary of each column with values for box mode, along with the
+    /// column, which is also its X value. In log scaled coordinates, like the
+    /// bounds.
+    pub(crate) fn boxes(&self) -> Vec<(usize, BoxStats)> {
+        let transform = TransformType::new(false, self.log_y());
+        (0..self.dataset.columns)
+            .filter_map(|col| {
+                let values: Vec<f64> = self.dataset.points[col]
+                    .iter()
+                    .take(self.dataset.rows)
+                    .filter(|point| !point.is_empty())
+                    .map(|point| point.scale_transform(transform).1)
+                    .collect();
+                BoxStats::new(&values).map(|stats| (col, stats))
+            })
+            .collect()
+    }
+
+    /// Count the points of each column falling in each cell of a grid of the
+    /// given size.
+    pub fn scaled_counters(&self, width: usize, height: usize) -> Counters {
+        let bounds = self.bounds(width, height);
         let mut counters = Vec::new();
         for col in 0..self.dataset.columns {
             let points = &self.dataset.points[col];
@@ -208,7 +497,7 @@ impl<'a> Plot<'a> {
                     continue;
                 }
 
-                let scaled_point = ScaledPoint::new_from_plot(*point, self, transform);
+                let scaled_point = ScaledPoint::new_from_bounds(*point, transform, bounds.clone());
                 let x = scaled_point.0;
                 let y = scaled_point.1;
 
@@ -221,43 +510,259 @@ impl<'a> Plot<'a> {
 
         Counters { counters }
     }
+
+    /// The number of points of any column falling in each cell of a grid of
+    /// the given size, for heatmap mode.
+    pub fn density(&self, width: usize, height: usize) -> HashMap<(i32, i32), u32> {
+        let mut density = HashMap::new();
+        for counts in self.scaled_counters(width, height).counters {
+            for (cell, count) in counts {
+                *density.entry(cell).or_insert(0) += count;
+            }
+        }
+        density
+    }
+
+    /// How far along the colour map to shade a cell holding a count of points,
+    /// when the fullest cell holds the max. Counts go on a log scale with
+    /// `--log-count`.
+    pub fn heat(&self, count: u32, max: u32) -> f64 {
+        if self.config.log_count {
+            (count as f64).ln_1p() / (max as f64).ln_1p()
+        } else {
+            count as f64 / max as f64
+        }
+    }
 }
 
-pub struct Counters {
-    counters: Vec<HashMap<(i32, i32), u32>>,
+/// Builder for a [`Plot`], for use outside the command line.
+pub struct PlotBuilder<'a> {
+    dataset: &'a DataSet,
+    config: Config,
 }
 
-pub fn draw(config: &Config, dataset: &DataSet) -> anyhow::Result<()> {
-    let plot = Plot::new(dataset, config)?;
+impl<'a> PlotBuilder<'a> {
+    /// Replace the whole configuration.
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn dimensions(mut self, width: usize, height: usize) -> Self {
+        self.config.dimensions = Some(Dimensions { width, height });
+        self
+    }
+
+    pub fn mode(mut self, mode: PlotType) -> Self {
+        self.config.mode = mode;
+        self
+    }
+
+    pub fn bar_layout(mut self, bar_layout: BarLayout) -> Self {
+        self.config.bar_layout = bar_layout;
+        self
+    }
+
+    pub fn interpolate(mut self, interpolate: Interpolation) -> Self {
+        self.config.interpolate = interpolate;
+        self
+    }
+
+    pub fn log_x(mut self, log_x: bool) -> Self {
+        self.config.log_x = log_x;
+        self
+    }
+
+    pub fn log_y(mut self, log_y: bool) -> Self {
+        self.config.log_y = log_y;
+        self
+    }
+
+    pub fn log_count(mut self, log_count: bool) -> Self {
+        self.config.log_count = log_count;
+        self
+    }
+
+    pub fn regression(mut self, regression: bool) -> Self {
+        self.config.regression = regression;
+        self
+    }
+
+    pub fn axis(mut self, axis: bool) -> Self {
+        self.config.axis = axis;
+        self
+    }
 
-    match config.output_type {
-        OutputType::Ascii => ascii_plot(&plot),
-        OutputType::Braille => braille_plot(&plot),
-        OutputType::Svg => {
-            // TODO: Configurable theme
-            let theme: SvgTheme = SvgTheme {
-                line_width: 2.0,
-                border_width: 2.0,
-                axis_width: 2.0,
-                bg_color: "black".into(),
-                border_color: "white".into(),
-                axis_color: "lightgray".into(),
-                colors: vec![
-                    "#377eb8".into(),
-                    "#e41a1c".into(),
-                    "#4daf4a".into(),
-                    "#984ea3".into(),
-                    "#ff7f00".into(),
-                    "#ffff33".into(),
-                    "#a65628".into(),
-                    "#f781bf".into(),
-                    "#999999".into(),
-                ],
-            };
-
-            svg_plot(&plot, &theme);
+    pub fn x_label(mut self, label: impl Into<String>) -> Self {
+        self.config.x_label = Some(label.into());
+        self
+    }
+
+    pub fn y_label(mut self, label: impl Into<String>) -> Self {
+        self.config.y_label = Some(label.into());
+        self
+    }
+
+    pub fn legend(mut self, legend: LegendPosition) -> Self {
+        self.config.legend = legend;
+        self
+    }
+
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.config.theme = theme;
+        self
+    }
+
+    /// Calculate the bounds of the plot. Fails if there is nothing to plot, or
+    /// if a log scale is requested for non-positive values.
+    pub fn build(self) -> anyhow::Result<Plot<'a>> {
+        Plot::new(self.dataset, &self.config)
+    }
+}
+
+/// A bar of bar mode, in the same log scaled coordinates as the plot's bounds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bar {
+    pub column: usize,
+    pub left: f64,
+    pub right: f64,
+    /// Where the bar starts: the baseline, or the top of the bar below it in a
+    /// stack.
+    pub base: f64,
+    /// Where the bar ends, which is below the base for negative values.
+    pub value: f64,
+}
+
+/// A run of consecutive values of a column in area mode.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Area {
+    pub column: usize,
+    pub points: Vec<AreaPoint>,
+}
+
+/// A value of an area, with the Y value it's filled from. In log scaled
+/// coordinates, like the bounds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AreaPoint {
+    pub x: f64,
+    pub base: f64,
+    pub value: f64,
+}
+
+/// A point on the path of a line, in whole cells or exactly.
+trait PathPoint: Copy {
+    /// The point across from this one, at the height of another.
+    fn at_height_of(self, other: Self) -> Self;
+    /// The point halfway across from this one to another, at this one's height.
+    fn halfway_to(self, other: Self) -> Self;
+}
+
+impl PathPoint for ScaledPoint {
+    fn at_height_of(self, other: Self) -> Self {
+        ScaledPoint(self.0, other.1)
+    }
+
+    fn halfway_to(self, other: Self) -> Self {
+        ScaledPoint(self.0 + (other.0 - self.0) / 2, self.1)
+    }
+}
+
+impl PathPoint for ExactPoint {
+    fn at_height_of(self, other: Self) -> Self {
+        ExactPoint(self.0, other.1)
+    }
+
+    fn halfway_to(self, other: Self) -> Self {
+        ExactPoint((self.0 + other.0) / 2.0, self.1)
+    }
+}
+
+/// The corners of the path joining up a run of points in line mode, with those
+/// of the steps added between points for the step interpolations. Smooth curves
+/// are left to the renderers that can draw them, so are straight lines here.
+fn interpolate<P: PathPoint>(run: &[P], interpolation: Interpolation) -> Vec<P> {
+    let Some(&first) = run.first() else {
+        return Vec::new();
+    };
+
+    let mut path = vec![first];
+    for pair in run.windows(2) {
+        let (from, to) = (pair[0], pair[1]);
+        match interpolation {
+            Interpolation::Linear | Interpolation::Monotone | Interpolation::CatmullRom => {}
+            Interpolation::StepBefore => path.push(from.at_height_of(to)),
+            Interpolation::StepAfter => path.push(to.at_height_of(from)),
+            Interpolation::StepMid => {
+                let mid = from.halfway_to(to);
+                path.push(mid);
+                path.push(mid.at_height_of(to));
+            }
         }
+        path.push(to);
+    }
+
+    path
+}
+
+/// The total of the negative and of the positive values in a row, which bound
+/// its stack of bars.
+fn stack_heights(dataset: &DataSet, row: usize) -> (f64, f64) {
+    let values = (0..dataset.columns)
+        .map(|col| dataset.points[col][row])
+        .filter(|point| !point.is_empty())
+        .map(|point| point.1);
+    values.fold((0.0, 0.0), |(below, above), y| {
+        if y < 0.0 {
+            (below + y, above)
+        } else {
+            (below, above + y)
+        }
+    })
+}
+
+/// Whether X values are timestamps. A histogram's X axis holds the values of
+/// the samples instead, which are counted whatever their times.
+fn time_x(config: &Config) -> bool {
+    config.x_time.is_some() && config.mode != PlotType::Histogram
+}
+
+/// The space given to the bars at each X value: the smallest gap between X
+/// values, in log scaled coordinates.
+fn bar_slot(dataset: &DataSet, transform: TransformType) -> f64 {
+    let mut xs: Vec<f64> = dataset
+        .points
+        .iter()
+        .flat_map(|column| column.iter().take(dataset.rows))
+        // Empty bins and missing values still have a place on the X axis.
+        .filter(|point| !point.0.is_nan())
+        .map(|point| point.scale_transform(transform).0)
+        .collect();
+    xs.sort_by(f64::total_cmp);
+
+    xs.windows(2)
+        .map(|pair| pair[1] - pair[0])
+        .filter(|gap| *gap > 0.0)
+        .min_by(f64::total_cmp)
+        .unwrap_or(1.0)
+}
+
+pub struct Counters {
+    counters: Vec<HashMap<(i32, i32), u32>>,
+}
+
+/// Get the renderer for an output type.
+pub fn renderer(output_type: OutputType) -> Box<dyn Renderer> {
+    match output_type {
+        OutputType::Ascii => Box::new(AsciiPlot),
+        OutputType::Braille => Box::new(BraillePlot),
+        OutputType::Svg => Box::new(SvgPlot),
+        OutputType::Html => Box::new(HtmlPlot),
     }
+}
+
+pub fn draw(config: &Config, dataset: &DataSet, out: &mut dyn Write) -> anyhow::Result<()> {
+    let plot = Plot::new(dataset, config)?;
+    renderer(config.output_type).render(&plot, out)?;
 
     Ok(())
 }
@@ -314,8 +819,10 @@ mod test {
 
     #[test]
     fn bounds_negative_quadrant() {
-        let mut cfg = Config::default();
-        cfg.x_column = true;
+        let cfg = Config {
+            x_column: true,
+            ..Default::default()
+        };
         let mut dataset = DataSet::default();
         let lines = ["-5 -50", "-3 -30", "-1 -10"];
 
@@ -334,8 +841,10 @@ mod test {
 
     #[test]
     fn bounds_4q() {
-        let mut cfg = Config::default();
-        cfg.x_column = true;
+        let cfg = Config {
+            x_column: true,
+            ..Default::default()
+        };
         let mut dataset = DataSet::default();
         let lines = ["-5 -50", "-49 49", "0 0", "10 10"];
 
@@ -355,8 +864,10 @@ mod test {
 
     #[test]
     fn bounds_diff_quadrants_for_columns() {
-        let mut cfg = Config::default();
-        cfg.x_column = true;
+        let cfg = Config {
+            x_column: true,
+            ..Default::default()
+        };
         let mut dataset = DataSet::default();
         let lines = ["-50 -50 50", "-49 -49 49", "-48 -48 48", "-47 -47 47"];
 
@@ -375,8 +886,10 @@ mod test {
 
     #[test]
     fn bounds_too_distant_to_touch_axis() {
-        let mut cfg = Config::default();
-        cfg.x_column = true;
+        let cfg = Config {
+            x_column: true,
+            ..Default::default()
+        };
         let mut dataset = DataSet::default();
         let lines = ["-5000 -5000", "-4900 -4900", "-4800 -4800"];
 
@@ -395,8 +908,10 @@ mod test {
 
     #[test]
     fn bounds_log_basic() {
-        let mut cfg = Config::default();
-        cfg.log_y = true;
+        let cfg = Config {
+            log_y: true,
+            ..Default::default()
+        };
 
         let lines = ["1214", "358", "316", "187", "186", "93", "63", "11"];
 
@@ -414,8 +929,10 @@ mod test {
 
     #[test]
     fn bounds_log_distant() {
-        let mut cfg = Config::default();
-        cfg.log_y = true;
+        let cfg = Config {
+            log_y: true,
+            ..Default::default()
+        };
 
         let lines = ["1214", "358", "316", "187", "186"];
 
@@ -455,4 +972,231 @@ mod test {
 
         assert_ne!(plot_info.y_range(), 0.0);
     }
+
+    #[test]
+    fn render_to_buffer() {
+        let cfg = Config {
+            dimensions: Some("20x10".parse().unwrap()),
+            ..Default::default()
+        };
+        let mut dataset = DataSet::default();
+        let plot = read_lines(&cfg, &mut dataset, &["1", "2", "3"]);
+
+        for output_type in [OutputType::Ascii, OutputType::Braille] {
+            let mut out = Vec::new();
+            renderer(output_type).render(&plot, &mut out).unwrap();
+            let out = String::from_utf8(out).unwrap();
+
+            // Header, followed by one line per row.
+            assert_eq!(out.lines().count(), 11);
+        }
+
+        let mut out = Vec::new();
+        renderer(OutputType::Svg).render(&plot, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("<svg "));
+        assert!(out.trim_end().ends_with("</svg>"));
+    }
+
+    #[test]
+    fn grouped_bars() {
+        let cfg = Config {
+            mode: PlotType::Bar,
+            ..Default::default()
+        };
+        let mut dataset = DataSet::default();
+        let plot = read_lines(&cfg, &mut dataset, &["2 -1", "4 3"]);
+
+        // Room is left for the bars either side of the first and last X value.
+        assert_eq!(plot.x_min, -0.5);
+        assert_eq!(plot.x_max, 1.5);
+
+        let bars = plot.bars();
+        assert_eq!(bars.len(), 4);
+        assert_eq!(bars[1].column, 1);
+        assert!((bars[1].left - 0.0).abs() < 1e-9);
+        assert!((bars[1].right - 0.4).abs() < 1e-9);
+        assert_eq!((bars[1].base, bars[1].value), (0.0, -1.0));
+    }
+
+    #[test]
+    fn stacked_bars() {
+        let cfg = Config {
+            mode: PlotType::Bar,
+            bar_layout: BarLayout::Stacked,
+            ..Default::default()
+        };
+        let mut dataset = DataSet::default();
+        let plot = read_lines(&cfg, &mut dataset, &["2 -1 3", "4 3 1"]);
+
+        // Bounds cover the whole stack, above and below the axis.
+        assert_eq!(plot.y_min, -1.0);
+        assert_eq!(plot.y_max, 8.0);
+
+        let stack: Vec<_> = plot.bars()[..3]
+            .iter()
+            .map(|bar| (bar.base, bar.value))
+            .collect();
+        assert_eq!(stack, [(0.0, 2.0), (0.0, -1.0), (2.0, 5.0)]);
+
+        let cfg = Config { log_y: true, ..cfg };
+        assert!(Plot::new(&dataset, &cfg).is_err());
+    }
+
+    #[test]
+    fn boxes() {
+        let cfg = Config {
+            mode: PlotType::Box,
+            ..Default::default()
+        };
+        let mut dataset = DataSet::default();
+        let plot = read_lines(&cfg, &mut dataset, &["10 20", "12 22", "14", "40 24"]);
+
+        // A unit for each column, and the Y axis kept to the values.
+        assert_eq!((plot.x_min, plot.x_max), (-0.5, 1.5));
+        assert_eq!((plot.y_min, plot.y_max), (10.0, 40.0));
+
+        let boxes = plot.boxes();
+        assert_eq!(boxes.len(), 2);
+        assert_eq!(boxes[0].1.median, 13.0);
+        assert_eq!(boxes[0].1.outliers, [40.0]);
+        assert_eq!(boxes[1].0, 1);
+
+        let labels: Vec<_> = plot
+            .x_tick_values(10)
+            .into_iter()
+            .map(|t| t.label)
+            .collect();
+        assert_eq!(labels, ["column 1", "column 2"]);
+    }
+
+    #[test]
+    fn areas() {
+        let cfg = Config {
+            mode: PlotType::StackedArea,
+            x_column: true,
+            ..Default::default()
+        };
+        let mut dataset = DataSet::default();
+        let lines = ["1 2 3", "2 4", "3 1 -1", "4 5 2"];
+        let plot = read_lines(&cfg, &mut dataset, &lines);
+
+        // The stack reaches the total of the values.
+        assert_eq!((plot.y_min, plot.y_max), (-1.0, 7.0));
+
+        let areas = plot.areas();
+        let spans = |area: &Area| -> Vec<_> {
+            area.points.iter().map(|p| (p.x, p.base, p.value)).collect()
+        };
+        // The empty value splits the second column, and so does its change of sign.
+        assert_eq!(areas.len(), 4);
+        assert_eq!(
+            spans(&areas[0]),
+            [
+                (1.0, 0.0, 2.0),
+                (2.0, 0.0, 4.0),
+                (3.0, 0.0, 1.0),
+                (4.0, 0.0, 5.0)
+            ]
+        );
+        assert_eq!(spans(&areas[1]), [(1.0, 2.0, 5.0)]);
+        assert_eq!(spans(&areas[2]), [(3.0, 0.0, -1.0)]);
+        assert_eq!(spans(&areas[3]), [(4.0, 5.0, 7.0)]);
+
+        let cfg = Config {
+            mode: PlotType::Area,
+            ..cfg.clone()
+        };
+        let plot = Plot::new(&dataset, &cfg).unwrap();
+        let areas = plot.areas();
+        assert_eq!(spans(&areas[2]), [(3.0, 0.0, -1.0), (4.0, 0.0, 2.0)]);
+
+        let cfg = Config {
+            mode: PlotType::StackedArea,
+            log_y: true,
+            ..cfg
+        };
+        let mut dataset = DataSet::default();
+        process_line(&cfg, &mut dataset, "1 2 3", 0);
+        let err = Plot::new(&dataset, &cfg).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Stacked areas can't be drawn on a log scale"
+        );
+    }
+
+    #[test]
+    fn stacked_areas_change_sign() {
+        let cfg = Config {
+            mode: PlotType::StackedArea,
+            x_column: true,
+            ..Default::default()
+        };
+        let mut dataset = DataSet::default();
+        let plot = read_lines(&cfg, &mut dataset, &["1 2 -1", "2 -1 3", "3 -2 2"]);
+
+        let spans: Vec<Vec<_>> = plot
+            .areas()
+            .iter()
+            .map(|area| area.points.iter().map(|p| (p.x, p.base, p.value)).collect())
+            .collect();
+        assert_eq!(
+            spans,
+            [
+                vec![(1.0, 0.0, 2.0)],
+                vec![(2.0, 0.0, -1.0), (3.0, 0.0, -2.0)],
+                vec![(1.0, 0.0, -1.0)],
+                vec![(2.0, 0.0, 3.0), (3.0, 0.0, 2.0)],
+            ]
+        );
+    }
+
+    #[test]
+    fn step_interpolation() {
+        let run = [ScaledPoint(0, 4), ScaledPoint(4, 0), ScaledPoint(6, 2)];
+        let path = |interpolation| interpolate(&run, interpolation);
+
+        assert_eq!(path(Interpolation::Linear), run);
+        assert_eq!(
+            path(Interpolation::StepAfter),
+            [(0, 4), (4, 4), (4, 0), (6, 0), (6, 2)].map(|(x, y)| ScaledPoint(x, y))
+        );
+        assert_eq!(
+            path(Interpolation::StepBefore),
+            [(0, 4), (0, 0), (4, 0), (4, 2), (6, 2)].map(|(x, y)| ScaledPoint(x, y))
+        );
+        assert_eq!(
+            path(Interpolation::StepMid),
+            [(0, 4), (2, 4), (2, 0), (4, 0), (5, 0), (5, 2), (6, 2)]
+                .map(|(x, y)| ScaledPoint(x, y))
+        );
+        assert_eq!(interpolate(&run[..1], Interpolation::StepMid), run[..1]);
+    }
+
+    #[test]
+    fn density() {
+        let cfg = Config {
+            mode: PlotType::Heatmap,
+            x_column: true,
+            ..Default::default()
+        };
+        let mut dataset = DataSet::default();
+        let plot = read_lines(&cfg, &mut dataset, &["0 0 0", "0 0", "100 100 100"]);
+
+        // Both columns are counted into the same cells.
+        let density = plot.density(40, 20);
+        let mut counts: Vec<_> = density.values().copied().collect();
+        counts.sort();
+        assert_eq!(counts, [2, 3]);
+
+        assert_eq!(plot.heat(3, 3), 1.0);
+        assert_eq!(plot.heat(1, 4), 0.25);
+
+        let cfg = Config {
+            log_count: true,
+            ..cfg.clone()
+        };
+        let plot = Plot::new(&dataset, &cfg).unwrap();
+        assert_eq!(plot.heat(1, 3), 0.5);
+    }
 }
@@ -1,77 +1,146 @@
+use std::io::Write;
+
 use crate::{
-    config::PlotType,
-    draw::Plot,
-    scale::{ScaledPoint, TransformType},
+    config::{Interpolation, LegendPosition, PlotType},
+    draw::{interpolate, Area, Plot, Renderer},
+    scale::{Bounds, ExactPoint, ScaledPoint, TransformType},
+    stats::BoxStats,
+    theme::{hex, Marker, Theme},
+    ticks::{value_ticks, Tick},
     types::Point,
 };
 
-pub struct SvgTheme {
-    pub bg_color: String,
-    pub border_color: String,
-    pub axis_color: String,
-    pub colors: Vec<String>,
-    pub line_width: f64,
-    pub axis_width: f64,
-    pub border_width: f64,
-}
+const FONT_SIZE: f64 = 12.0;
+/// Rough width of a character of the monospace font, relative to its size.
+const CHAR_WIDTH: f64 = 0.6;
+const LEGEND_PAD: f64 = FONT_SIZE / 2.0;
+const LEGEND_ROW: f64 = 1.5 * FONT_SIZE;
+/// Length of the line showing each column's colour in the legend.
+const SWATCH_WIDTH: f64 = 20.0;
+/// Size in pixels of the square cells points are counted into in heatmap mode.
+const HEAT_CELL: i32 = 8;
+/// Width of the colour scale beside a heatmap.
+const COLOR_BAR_WIDTH: f64 = 12.0;
+/// How opaque the fill of each area is, so that those behind show through.
+const AREA_OPACITY: f64 = 0.5;
+
+/// Renders a plot as an SVG document.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SvgPlot;
 
-impl SvgTheme {
-    pub fn get_color(&self, col: usize) -> &str {
-        self.colors.get(col).unwrap_or(
-            self.colors
-                .last()
-                .expect("SvgTheme::get_color: no colors defined"),
-        )
+impl Renderer for SvgPlot {
+    fn render(&self, plot: &Plot, out: &mut dyn Write) -> std::io::Result<()> {
+        svg_plot(plot, out)
     }
 }
 
-pub fn svg_plot(plot: &Plot, theme: &SvgTheme) {
-    print_header(plot.width(), plot.height());
-    print_frame(plot.width(), plot.height(), theme);
+fn svg_plot(plot: &Plot, out: &mut dyn Write) -> std::io::Result<()> {
+    let theme = &plot.config.theme;
+    let layout = Layout::new(plot, theme);
+
+    print_header(plot.width(), plot.height(), out)?;
+    print_frame(plot.width(), plot.height(), theme, out)?;
 
     if plot.config.axis {
-        print_axis(plot, theme);
+        print_ticks(&layout, theme, out)?;
+    }
+    print_axis_titles(plot, &layout, theme, out)?;
+
+    // Everything else is drawn relative to the plot area.
+    writeln!(
+        out,
+        r#"<g transform="translate({},{})">"#,
+        layout.left, layout.top
+    )?;
+
+    if plot.config.axis {
+        print_grid(&layout, theme, out)?;
+        print_axis(plot, &layout, theme, out)?;
     }
 
     let transform = TransformType::new(plot.log_x(), plot.log_y());
+    let bounds = layout.bounds(plot);
+    let counters = (plot.config.mode == PlotType::Count)
+        .then(|| plot.scaled_counters(layout.width, layout.height));
+    let bars = if plot.config.mode.is_bars() {
+        plot.bars()
+    } else {
+        Vec::new()
+    };
+    let boxes = if plot.config.mode == PlotType::Box {
+        plot.boxes()
+    } else {
+        Vec::new()
+    };
+    let areas = if plot.config.mode.is_areas() {
+        plot.areas()
+    } else {
+        Vec::new()
+    };
+    let heat_max = if plot.config.mode == PlotType::Heatmap {
+        Some(print_heatmap(plot, &layout, out)?)
+    } else {
+        None
+    };
 
     for c in 0..plot.dataset.columns {
-        let color = theme.get_color(c);
+        let color = hex(theme.series_color(c));
         let column = &plot.dataset.points[c];
 
-        if plot.config.mode == PlotType::Line {
-            let mut beginning_line = true;
+        if plot.config.mode.is_bars() {
+            for bar in bars.iter().filter(|bar| bar.column == c) {
+                // Bars are already log scaled.
+                let scale = |x, y| {
+                    ScaledPoint::new_from_bounds(Point(x, y), TransformType::None, bounds.clone())
+                };
+                let from = scale(bar.left, bar.base);
+                let to = scale(bar.right, bar.value);
+                print_bar(from, to, &color, out)?;
+            }
+        } else if plot.config.mode == PlotType::Box {
+            if let Some((_, stats)) = boxes.iter().find(|(col, _)| *col == c) {
+                let slot = layout.width as f64 / plot.dataset.columns as f64;
+                print_box(c, stats, &bounds, slot, theme, &color, out)?;
+            }
+        } else if plot.config.mode.is_areas() {
+            for area in areas.iter().filter(|area| area.column == c) {
+                print_area(area, &bounds, &color, theme, out)?;
+            }
+        } else if plot.config.mode == PlotType::Heatmap {
+            // Every column went into the heatmap above.
+        } else if plot.config.mode == PlotType::Line {
+            // Empty values break the line.
+            let mut runs = vec![Vec::new()];
             for p in column {
                 if p.is_empty() {
-                    if !beginning_line {
-                        end_polyline(color, theme.line_width);
-                    }
-                    beginning_line = true;
+                    runs.push(Vec::new());
                     continue;
                 }
-
-                if beginning_line {
-                    beginning_line = false;
-                    begin_polyline();
-                }
-
-                let sp = ScaledPoint::new_from_plot(*p, plot, transform);
-                polyline_point(sp.x(), sp.y());
+                let ep = ExactPoint::new_from_bounds(*p, transform, &bounds);
+                runs.last_mut().unwrap().push(ep);
             }
 
-            if !beginning_line {
-                end_polyline(color, theme.line_width);
+            let interpolation = plot.config.interpolate;
+            for run in runs.iter().filter(|run| !run.is_empty()) {
+                if interpolation.is_smooth() {
+                    print_curve(run, interpolation, &color, theme.line_width, out)?;
+                    continue;
+                }
+                begin_polyline(out)?;
+                for ep in interpolate(run, interpolation) {
+                    polyline_point(ep.0, ep.1, out)?;
+                }
+                end_polyline(&color, theme.line_width, out)?;
             }
         } else {
             for p in column {
-                let sp = ScaledPoint::new_from_plot(*p, plot, transform);
                 if p.is_empty() {
                     continue;
                 }
+                let sp = ScaledPoint::new_from_bounds(*p, transform, bounds.clone());
 
-                let point_size = 3.0;
-                if let PlotType::Count = plot.config.mode {
-                    let counters = &plot.counters();
+                let point_size = theme.marker_size;
+                if let Some(counters) = &counters {
                     let counter = counters.counters.get(c).unwrap();
                     let count = counter.get(&(sp.0, sp.1)).unwrap_or(&0);
                     let r = if plot.config.log_count {
@@ -79,172 +148,1019 @@ pub fn svg_plot(plot: &Plot, theme: &SvgTheme) {
                     } else {
                         *count as f64 + point_size
                     };
-                    print_circle(sp.x(), sp.y(), r, color);
+                    print_marker(sp.x(), sp.y(), r, theme, &color, out)?;
                 } else {
-                    print_circle(sp.x(), sp.y(), point_size, color);
+                    print_marker(sp.x(), sp.y(), point_size, theme, &color, out)?;
                 }
             }
         }
 
-        if plot.config.regression {
+        // A trend through the counts of a histogram, across the columns of a
+        // box plot, through values drawn on top of others, or through columns
+        // counted together into a heatmap, means nothing.
+        if plot.config.regression
+            && !matches!(
+                plot.config.mode,
+                PlotType::Histogram | PlotType::Box | PlotType::StackedArea | PlotType::Heatmap
+            )
+        {
             let regression = crate::regression::linear_regression(column, transform);
             if let Some(regression) = regression {
-                regression_line(plot, color, regression);
+                regression_line(plot, &bounds, &color, regression, out)?;
             }
         }
     }
 
-    end_svg();
+    writeln!(out, "</g>")?;
+
+    if let Some(max) = heat_max {
+        print_color_bar(plot, &layout, max, out)?;
+    }
+    print_legend(plot, &layout, theme, out)?;
+
+    end_svg(out)
+}
+
+/// Where the plot area sits in the image, leaving margins for the tick labels
+/// and axis titles, along with the ticks themselves.
+struct Layout {
+    left: usize,
+    top: usize,
+    width: usize,
+    height: usize,
+    tick_width: f64,
+    /// Baseline of the X axis title.
+    x_title_y: f64,
+    legend: Legend,
+    /// Top left corner of the legend, if there is one.
+    legend_origin: Option<(f64, f64)>,
+    /// Ticks with their distance in pixels from the left of the plot area.
+    x_ticks: Vec<(i32, Tick)>,
+    /// Ticks with their distance in pixels from the top of the plot area.
+    y_ticks: Vec<(i32, Tick)>,
+}
+
+impl Layout {
+    fn new(plot: &Plot, theme: &Theme) -> Self {
+        let axis = plot.config.axis;
+        let tick_width = 3.0 * theme.axis_width;
+        let title_size = 1.5 * FONT_SIZE;
+        let legend = Legend::new(plot);
+        let (legend_width, legend_height) = legend.size();
+
+        // Room for the labels of ticks at the very top and right of the plot.
+        let pad = if axis { FONT_SIZE } else { 0.0 };
+        let mut bottom = 0.0;
+        if axis {
+            bottom += tick_width + title_size;
+        }
+        if plot.config.x_label.is_some() {
+            bottom += title_size;
+        }
+        let legend_below = match legend.position {
+            LegendPosition::Bottom => legend_height,
+            _ => 0.0,
+        };
+        bottom += legend_below;
+
+        let top = pad as usize;
+        let height = plot
+            .height()
+            .saturating_sub(top + bottom.ceil() as usize)
+            .max(1);
+        let y_ticks = if axis {
+            let transform = TransformType::new(false, plot.log_y());
+            let bounds = plot.bounds(1, height);
+            value_ticks(plot.y_min(), plot.y_max(), plot.log_y(), height / 40)
+                .into_iter()
+                .map(|tick| {
+                    let p = Point(plot.x_min(), tick.value);
+                    let sp = ScaledPoint::new_from_bounds(p, transform, bounds.clone());
+                    (sp.y(), tick)
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let mut left = 0.0;
+        if axis {
+            let label_len = y_ticks
+                .iter()
+                .map(|(_, tick): &(i32, Tick)| tick.label.chars().count())
+                .max()
+                .unwrap_or(0);
+            left += label_len as f64 * FONT_SIZE * CHAR_WIDTH + tick_width + 4.0;
+        }
+        if plot.config.y_label.is_some() {
+            left += title_size;
+        }
+
+        let left = left.ceil() as usize;
+        let mut right = match legend.position {
+            LegendPosition::Outside => legend_width + 2.0 * LEGEND_PAD,
+            _ => pad,
+        };
+        if plot.config.mode == PlotType::Heatmap {
+            // No cell can hold more points than there are, so that many
+            // digits is room enough for the label at the top of the scale.
+            let points = plot.dataset.rows * plot.dataset.columns;
+            let digits = points.max(1).to_string().len() as f64;
+            right += COLOR_BAR_WIDTH + 2.0 * LEGEND_PAD + digits * FONT_SIZE * CHAR_WIDTH;
+        }
+        let width = plot
+            .width()
+            .saturating_sub(left + right.ceil() as usize)
+            .max(1);
+        let x_ticks = if axis {
+            // Leave room for labels such as "Oct 17 12:00" between the ticks.
+            let max_ticks = width / 80;
+            let ticks = plot.x_tick_values(max_ticks);
+            let transform = TransformType::new(plot.log_x(), false);
+            let bounds = plot.bounds(width, 1);
+            ticks
+                .into_iter()
+                .map(|tick| {
+                    let p = Point(tick.value, plot.y_min());
+                    let sp = ScaledPoint::new_from_bounds(p, transform, bounds.clone());
+                    (sp.x(), tick)
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let legend_origin = match legend.position {
+            _ if legend.labels.is_empty() => None,
+            LegendPosition::TopRight => Some((
+                (left + width) as f64 - legend_width - LEGEND_PAD,
+                top as f64 + LEGEND_PAD,
+            )),
+            LegendPosition::Bottom => Some((
+                left as f64 + (width as f64 - legend_width).max(0.0) / 2.0,
+                plot.height() as f64 - legend_height,
+            )),
+            LegendPosition::Outside => Some(((left + width) as f64 + LEGEND_PAD, top as f64)),
+            LegendPosition::None => None,
+        };
+
+        Layout {
+            left,
+            top,
+            width,
+            height,
+            tick_width,
+            x_title_y: plot.height() as f64 - legend_below - FONT_SIZE / 2.0,
+            legend,
+            legend_origin,
+            x_ticks,
+            y_ticks,
+        }
+    }
+
+    fn bounds(&self, plot: &Plot) -> Bounds {
+        plot.bounds(self.width, self.height)
+    }
 }
 
-fn print_header(width: usize, height: usize) {
-    println!(
+fn print_header(width: usize, height: usize, out: &mut dyn Write) -> std::io::Result<()> {
+    writeln!(
+        out,
         r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" version="1.1">"#,
         width, height
-    );
+    )
 }
 
-fn print_frame(width: usize, height: usize, theme: &SvgTheme) {
-    println!(
+fn print_frame(
+    width: usize,
+    height: usize,
+    theme: &Theme,
+    out: &mut dyn Write,
+) -> std::io::Result<()> {
+    writeln!(
+        out,
         r#"<rect x="0" y="0" width="{}" height="{}" fill="{}" stroke="{}" stroke-width="{}"/>"#,
-        width, height, theme.bg_color, theme.border_color, theme.border_width
-    );
+        width,
+        height,
+        hex(theme.background),
+        hex(theme.foreground),
+        theme.border_width
+    )
 }
 
-fn begin_polyline() {
-    println!(r#"<polyline points=""#);
+fn begin_polyline(out: &mut dyn Write) -> std::io::Result<()> {
+    writeln!(out, r#"<polyline points=""#)
 }
 
-fn polyline_point(x: i32, y: i32) {
-    print!("{},{} ", x, y);
+fn polyline_point(x: f64, y: f64, out: &mut dyn Write) -> std::io::Result<()> {
+    write!(out, "{},{} ", coord(x), coord(y))
 }
 
-fn end_polyline(color: &str, line_width: f64) {
-    println!(
+/// A coordinate to a hundredth of a pixel, which is finer than anyone can see.
+fn coord(value: f64) -> f64 {
+    // Adding zero turns a negative zero positive.
+    (value * 100.0).round() / 100.0 + 0.0
+}
+
+/// Join up a run of points with a smooth curve, as a path of cubic Bézier
+/// segments.
+fn print_curve(
+    run: &[ExactPoint],
+    interpolation: Interpolation,
+    color: &str,
+    line_width: f64,
+    out: &mut dyn Write,
+) -> std::io::Result<()> {
+    write!(out, r#"<path d="M{},{}"#, coord(run[0].0), coord(run[0].1))?;
+    for (to, (c1, c2)) in run[1..].iter().zip(curve_controls(run, interpolation)) {
+        write!(
+            out,
+            " C{},{} {},{} {},{}",
+            coord(c1.0),
+            coord(c1.1),
+            coord(c2.0),
+            coord(c2.1),
+            coord(to.0),
+            coord(to.1)
+        )?;
+    }
+    writeln!(
+        out,
         r#"" fill="none" stroke="{}" stroke-width="{}"/>"#,
         color, line_width
-    );
+    )
 }
 
-fn print_circle(x: i32, y: i32, r: f64, color: &str) {
-    println!(
-        r#"<circle cx="{}" cy="{}" r="{}" stroke="{}"/>"#,
-        x, y, r, color
-    );
+/// The two control points of the Bézier segment from each point of a run to
+/// the next, for a smooth interpolation.
+fn curve_controls(
+    run: &[ExactPoint],
+    interpolation: Interpolation,
+) -> Vec<(ExactPoint, ExactPoint)> {
+    let last = run.len().saturating_sub(1);
+    if interpolation == Interpolation::CatmullRom {
+        // A uniform Catmull-Rom spline, with the end points repeated to give
+        // the first and last segments a neighbour on each side.
+        return (0..last)
+            .map(|i| {
+                let (p0, p1) = (run[i.saturating_sub(1)], run[i]);
+                let (p2, p3) = (run[i + 1], run[(i + 2).min(last)]);
+                (
+                    ExactPoint(p1.0 + (p2.0 - p0.0) / 6.0, p1.1 + (p2.1 - p0.1) / 6.0),
+                    ExactPoint(p2.0 - (p3.0 - p1.0) / 6.0, p2.1 - (p3.1 - p1.1) / 6.0),
+                )
+            })
+            .collect();
+    }
+
+    let tangents = monotone_tangents(run);
+    (0..last)
+        .map(|i| {
+            let (p1, p2) = (run[i], run[i + 1]);
+            let third = (p2.0 - p1.0) / 3.0;
+            (
+                ExactPoint(p1.0 + third, p1.1 + tangents[i] * third),
+                ExactPoint(p2.0 - third, p2.1 - tangents[i + 1] * third),
+            )
+        })
+        .collect()
 }
 
-fn print_axis(plot: &Plot, theme: &SvgTheme) {
-    let tick_width = 3.0 * theme.axis_width;
-    let (x_axis, y_axis) = plot.axis_positions();
+/// The slope of a monotone cubic curve through each point of a run, by the
+/// Fritsch–Butland method. The slope is flat at each peak and trough, and
+/// otherwise a weighted harmonic mean of the slopes either side, which keeps
+/// the curve from overshooting.
+fn monotone_tangents(run: &[ExactPoint]) -> Vec<f64> {
+    let widths: Vec<f64> = run.windows(2).map(|pair| pair[1].0 - pair[0].0).collect();
+    let secants: Vec<f64> = run
+        .windows(2)
+        .zip(&widths)
+        .map(|(pair, &width)| {
+            if width == 0.0 {
+                0.0
+            } else {
+                (pair[1].1 - pair[0].1) / width
+            }
+        })
+        .collect();
+
+    let Some((&first, &last)) = secants.first().zip(secants.last()) else {
+        return vec![0.0; run.len()];
+    };
+    let mut tangents = vec![first];
+    for i in 1..secants.len() {
+        let (d0, d1) = (secants[i - 1], secants[i]);
+        let (h0, h1) = (widths[i - 1], widths[i]);
+        let tangent = if d0 * d1 <= 0.0 {
+            0.0
+        } else {
+            3.0 * (h0 + h1) / ((2.0 * h1 + h0) / d0 + (h1 + 2.0 * h0) / d1)
+        };
+        tangents.push(tangent);
+    }
+    tangents.push(last);
+
+    tangents
+}
+
+fn end_polyline(color: &str, line_width: f64, out: &mut dyn Write) -> std::io::Result<()> {
+    writeln!(
+        out,
+        r#"" fill="none" stroke="{}" stroke-width="{}"/>"#,
+        color, line_width
+    )
+}
+
+/// Shade squares of the plot area by how many points of any column fall in
+/// them, returning the most in any square for the colour scale.
+fn print_heatmap(plot: &Plot, layout: &Layout, out: &mut dyn Write) -> std::io::Result<u32> {
+    let mut cells = std::collections::BTreeMap::new();
+    for ((x, y), count) in plot.density(layout.width, layout.height) {
+        let cell = (x.div_euclid(HEAT_CELL), y.div_euclid(HEAT_CELL));
+        *cells.entry(cell).or_insert(0) += count;
+    }
+
+    let max = cells.values().copied().max().unwrap_or(1);
+    for ((x, y), &count) in &cells {
+        let color = plot
+            .config
+            .colormap
+            .color(plot.heat(count, max), &plot.config.theme);
+        // Squares along the right and bottom edges are cut off by the plot area.
+        let (left, top) = (x * HEAT_CELL, y * HEAT_CELL);
+        writeln!(
+            out,
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+            left,
+            top,
+            HEAT_CELL.min(layout.width as i32 - left),
+            HEAT_CELL.min(layout.height as i32 - top),
+            hex(color)
+        )?;
+    }
+
+    Ok(max)
+}
+
+/// The colour map of a heatmap as a bar to the right of the plot area, labelled
+/// with no points at the bottom and the most in any square at the top.
+fn print_color_bar(
+    plot: &Plot,
+    layout: &Layout,
+    max: u32,
+    out: &mut dyn Write,
+) -> std::io::Result<()> {
+    let theme = &plot.config.theme;
+    let x = (layout.left + layout.width) as f64 + LEGEND_PAD;
+    let (top, bottom) = (layout.top as f64, (layout.top + layout.height) as f64);
+
+    writeln!(
+        out,
+        r#"<defs><linearGradient id="colormap" x1="0" y1="1" x2="0" y2="0">"#
+    )?;
+    for i in 0..=4 {
+        let t = i as f64 / 4.0;
+        writeln!(
+            out,
+            r#"<stop offset="{}" stop-color="{}"/>"#,
+            t,
+            hex(plot.config.colormap.color(t, theme))
+        )?;
+    }
+    writeln!(out, "</linearGradient></defs>")?;
+    writeln!(
+        out,
+        r#"<rect x="{}" y="{}" width="{}" height="{}" fill="url(#colormap)" stroke="{}" stroke-width="1"/>"#,
+        x,
+        top,
+        COLOR_BAR_WIDTH,
+        layout.height,
+        hex(theme.axis)
+    )?;
+
+    let label_x = x + COLOR_BAR_WIDTH + LEGEND_PAD / 2.0;
+    print_text(
+        label_x,
+        top + FONT_SIZE,
+        "start",
+        &max.to_string(),
+        theme,
+        out,
+    )?;
+    print_text(label_x, bottom, "start", "0", theme, out)
+}
+
+/// Fill an area between its values and its base, with a line along the values.
+fn print_area(
+    area: &Area,
+    bounds: &Bounds,
+    color: &str,
+    theme: &Theme,
+    out: &mut dyn Write,
+) -> std::io::Result<()> {
+    // Areas are already log scaled.
+    let scale = |x, y| ExactPoint::new_from_bounds(Point(x, y), TransformType::None, bounds);
+    let values: Vec<ExactPoint> = area.points.iter().map(|p| scale(p.x, p.value)).collect();
+    let bases = area.points.iter().rev().map(|p| scale(p.x, p.base));
+
+    write!(out, r#"<polygon points=""#)?;
+    for ep in values.iter().copied().chain(bases) {
+        polyline_point(ep.0, ep.1, out)?;
+    }
+    writeln!(
+        out,
+        r#"" fill="{}" fill-opacity="{}" stroke="none"/>"#,
+        color, AREA_OPACITY
+    )?;
+
+    begin_polyline(out)?;
+    for ep in &values {
+        polyline_point(ep.0, ep.1, out)?;
+    }
+    end_polyline(color, theme.line_width, out)
+}
+
+/// Draw a bar between two opposite corners, at least a pixel wide.
+fn print_bar(
+    from: ScaledPoint,
+    to: ScaledPoint,
+    color: &str,
+    out: &mut dyn Write,
+) -> std::io::Result<()> {
+    writeln!(
+        out,
+        r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+        from.x().min(to.x()),
+        from.y().min(to.y()),
+        (to.x() - from.x()).abs().max(1),
+        (to.y() - from.y()).abs(),
+        color
+    )
+}
+
+/// Draw a box and whiskers for a column, in a slot `slot` pixels wide, with a
+/// heavier line at the median and the theme's marker at each outlier.
+fn print_box(
+    col: usize,
+    stats: &BoxStats,
+    bounds: &Bounds,
+    slot: f64,
+    theme: &Theme,
+    color: &str,
+    out: &mut dyn Write,
+) -> std::io::Result<()> {
+    // The summary is already log scaled.
+    let scale =
+        |y| ScaledPoint::new_from_bounds(Point(col as f64, y), TransformType::None, bounds.clone());
+    let x = scale(stats.median).x();
+    let y = |value| scale(value).y();
+    let half = ((slot / 4.0) as i32).max(2);
+    let width = theme.line_width;
+
+    print_line((x, y(stats.max)), (x, y(stats.q3)), color, width, out)?;
+    print_line((x, y(stats.q1)), (x, y(stats.min)), color, width, out)?;
+    for cap in [stats.max, stats.min] {
+        print_line(
+            (x - half / 2, y(cap)),
+            (x + half / 2, y(cap)),
+            color,
+            width,
+            out,
+        )?;
+    }
+    writeln!(
+        out,
+        r#"<rect x="{}" y="{}" width="{}" height="{}" fill="none" stroke="{}" stroke-width="{}"/>"#,
+        x - half,
+        y(stats.q3),
+        2 * half,
+        y(stats.q1) - y(stats.q3),
+        color,
+        width
+    )?;
+    let median = y(stats.median);
+    print_line(
+        (x - half, median),
+        (x + half, median),
+        color,
+        2.0 * width,
+        out,
+    )?;
+
+    for &outlier in &stats.outliers {
+        print_marker(x, y(outlier), theme.marker_size, theme, color, out)?;
+    }
+
+    Ok(())
+}
+
+fn print_line(
+    (x1, y1): (i32, i32),
+    (x2, y2): (i32, i32),
+    color: &str,
+    width: f64,
+    out: &mut dyn Write,
+) -> std::io::Result<()> {
+    writeln!(
+        out,
+        r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="{}" />"#,
+        x1, y1, x2, y2, color, width
+    )
+}
+
+/// Draw the theme's marker, `r` pixels from its centre to its edge.
+fn print_marker(
+    x: i32,
+    y: i32,
+    r: f64,
+    theme: &Theme,
+    color: &str,
+    out: &mut dyn Write,
+) -> std::io::Result<()> {
+    match theme.marker {
+        Marker::Circle => writeln!(
+            out,
+            r#"<circle cx="{}" cy="{}" r="{}" fill="none" stroke="{}"/>"#,
+            x, y, r, color
+        ),
+        Marker::Square => writeln!(
+            out,
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="none" stroke="{}"/>"#,
+            x as f64 - r,
+            y as f64 - r,
+            2.0 * r,
+            2.0 * r,
+            color
+        ),
+        Marker::Cross => writeln!(
+            out,
+            r#"<path d="M{} {}L{} {}M{} {}L{} {}" stroke="{}"/>"#,
+            x as f64 - r,
+            y as f64 - r,
+            x as f64 + r,
+            y as f64 + r,
+            x as f64 - r,
+            y as f64 + r,
+            x as f64 + r,
+            y as f64 - r,
+            color
+        ),
+    }
+}
+
+/// Faint lines across the plot area at each tick.
+fn print_grid(layout: &Layout, theme: &Theme, out: &mut dyn Write) -> std::io::Result<()> {
+    for (x, _) in &layout.x_ticks {
+        writeln!(
+            out,
+            r#"<line x1="{}" y1="0" x2="{}" y2="{}" stroke="{}" stroke-width="1" />"#,
+            x,
+            x,
+            layout.height,
+            hex(theme.grid)
+        )?;
+    }
+    for (y, _) in &layout.y_ticks {
+        writeln!(
+            out,
+            r#"<line x1="0" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="1" />"#,
+            y,
+            layout.width,
+            y,
+            hex(theme.grid)
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Dashed lines through the origin, where it falls within the plot.
+fn print_axis(
+    plot: &Plot,
+    layout: &Layout,
+    theme: &Theme,
+    out: &mut dyn Write,
+) -> std::io::Result<()> {
+    let (x_axis, y_axis) = plot.scaled_axis_positions(layout.width, layout.height);
 
     if plot.draw_y_axis() {
-        // Y-axis
-        println!(
+        writeln!(
+            out,
             r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="{}" stroke-dasharray="2.5"/>"#,
             x_axis,
             0,
             x_axis,
-            plot.height(),
-            theme.axis_color,
+            layout.height,
+            hex(theme.axis),
             theme.axis_width
-        );
-
-        // Ticks
-        let mut x0 = x_axis as f64 - tick_width;
-        let x1 = x_axis as f64 + tick_width;
-        if x0 > plot.width() as f64 {
-            x0 = 0.0;
-        }
-
-        let y_to = scale_tick(plot.height(), plot.y_range());
-
-        let mut hy = y_axis as f64 + y_to;
-        while hy < plot.height() as f64 {
-            println!(
-                r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="1" />"#,
-                x0, hy, x1, hy, theme.axis_color
-            );
-            hy += y_to;
-        }
-        let mut hy = y_axis as f64 - y_to;
-        while hy > 0.0 {
-            println!(
-                r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="1" />"#,
-                x0, hy, x1, hy, theme.axis_color
-            );
-            hy -= y_to;
-        }
+        )?;
     }
 
     if plot.draw_x_axis() {
-        // X-axis
-        println!(
+        writeln!(
+            out,
             r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="{}" stroke-dasharray="2.5" />"#,
             0,
             y_axis,
-            plot.width(),
+            layout.width,
             y_axis,
-            theme.axis_color,
+            hex(theme.axis),
             theme.axis_width
-        );
+        )?;
+    }
 
-        // Ticks
-        let mut y0 = y_axis as f64 - tick_width;
-        let y1 = y_axis as f64 + tick_width;
-        if y0 > plot.height() as f64 {
-            y0 = 0.0;
-        }
+    Ok(())
+}
 
-        let x_to = scale_tick(plot.width(), plot.x_range());
+/// Lines along the left and bottom of the plot area, with tick marks and their
+/// values in the margins.
+fn print_ticks(layout: &Layout, theme: &Theme, out: &mut dyn Write) -> std::io::Result<()> {
+    let left = layout.left as f64;
+    let top = layout.top as f64;
+    let bottom = top + layout.height as f64;
+    let right = left + layout.width as f64;
 
-        let mut wx = x_axis as f64 + x_to;
-        while wx < plot.width() as f64 {
-            println!(
-                r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="1" />"#,
-                wx, y0, wx, y1, theme.axis_color
-            );
-            wx += x_to;
-        }
-        let mut wx = x_axis as f64 - x_to;
-        while wx > 0.0 {
-            println!(
-                r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="1" />"#,
-                wx, y0, wx, y1, theme.axis_color
-            );
-            wx -= x_to;
-        }
+    writeln!(
+        out,
+        r#"<polyline points="{},{} {},{} {},{}" fill="none" stroke="{}" stroke-width="1" />"#,
+        left,
+        top,
+        left,
+        bottom,
+        right,
+        bottom,
+        hex(theme.axis)
+    )?;
+
+    for (x, tick) in &layout.x_ticks {
+        let x = left + *x as f64;
+        writeln!(
+            out,
+            r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="1" />"#,
+            x,
+            bottom,
+            x,
+            bottom + layout.tick_width,
+            hex(theme.axis)
+        )?;
+        print_text(
+            x,
+            bottom + layout.tick_width + FONT_SIZE,
+            "middle",
+            &tick.label,
+            theme,
+            out,
+        )?;
+    }
+
+    for (y, tick) in &layout.y_ticks {
+        let y = top + *y as f64;
+        writeln!(
+            out,
+            r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="1" />"#,
+            left - layout.tick_width,
+            y,
+            left,
+            y,
+            hex(theme.axis)
+        )?;
+        // Nudged down to centre the text on the tick.
+        print_text(
+            left - layout.tick_width - 2.0,
+            y + FONT_SIZE / 3.0,
+            "end",
+            &tick.label,
+            theme,
+            out,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Titles centred below the X tick labels and left of the Y tick labels.
+fn print_axis_titles(
+    plot: &Plot,
+    layout: &Layout,
+    theme: &Theme,
+    out: &mut dyn Write,
+) -> std::io::Result<()> {
+    if let Some(label) = &plot.config.x_label {
+        let x = layout.left as f64 + layout.width as f64 / 2.0;
+        print_text(x, layout.x_title_y, "middle", label, theme, out)?;
     }
+
+    if let Some(label) = &plot.config.y_label {
+        let x = FONT_SIZE;
+        let y = layout.top as f64 + layout.height as f64 / 2.0;
+        writeln!(
+            out,
+            r#"<text x="{}" y="{}" fill="{}" font-family="monospace" font-size="{}" text-anchor="middle" transform="rotate(-90 {} {})">{}</text>"#,
+            x,
+            y,
+            hex(theme.foreground),
+            FONT_SIZE,
+            x,
+            y,
+            escape_xml(label)
+        )?;
+    }
+
+    Ok(())
 }
 
-fn regression_line(plot: &Plot, color: &str, regression: (f64, f64)) {
+fn print_text(
+    x: f64,
+    y: f64,
+    anchor: &str,
+    text: &str,
+    theme: &Theme,
+    out: &mut dyn Write,
+) -> std::io::Result<()> {
+    writeln!(
+        out,
+        r#"<text x="{}" y="{}" fill="{}" font-family="monospace" font-size="{}" text-anchor="{}">{}</text>"#,
+        x,
+        y,
+        hex(theme.foreground),
+        FONT_SIZE,
+        anchor,
+        escape_xml(text)
+    )
+}
+
+fn regression_line(
+    plot: &Plot,
+    bounds: &Bounds,
+    color: &str,
+    regression: (f64, f64),
+    out: &mut dyn Write,
+) -> std::io::Result<()> {
     let (slope, intercept) = regression;
 
     let p0 = Point(plot.x_min(), intercept + slope * plot.x_min());
     let p1 = Point(plot.x_max(), intercept + slope * plot.x_max());
 
     // Already scaled, so no need to scale again. Just need to create a ScaledPoint.
-    let p0 = ScaledPoint::new_from_plot(p0, plot, crate::scale::TransformType::None);
-    let p1 = ScaledPoint::new_from_plot(p1, plot, crate::scale::TransformType::None);
+    let p0 = ScaledPoint::new_from_bounds(p0, TransformType::None, bounds.clone());
+    let p1 = ScaledPoint::new_from_bounds(p1, TransformType::None, bounds.clone());
 
-    println!(
+    writeln!(
+        out,
         r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="{}" stroke-dasharray="5" />"#,
         p0.x(),
         p0.y(),
         p1.x(),
         p1.y(),
         color,
-        2
-    );
+        plot.config.theme.line_width
+    )
 }
 
-fn end_svg() {
-    println!("</svg>");
+/// The key of colours to columns.
+struct Legend {
+    position: LegendPosition,
+    labels: Vec<String>,
 }
 
-fn scale_tick(width: usize, range: f64) -> f64 {
-    let rounded = range.log10().ceil().powf(10.0);
-    let div = if range < rounded / 2.0 { 20.0 } else { 10.0 };
-    let step = rounded / div;
+impl Legend {
+    /// Columns are listed by name where the input had a header, and otherwise
+    /// by the input field they came from.
+    fn new(plot: &Plot) -> Self {
+        let dataset = plot.dataset();
+        // A heatmap counts the columns together, and has a colour scale instead.
+        let labels = if plot.config.mode == PlotType::Heatmap {
+            Vec::new()
+        } else {
+            (0..dataset.columns)
+                .map(|c| dataset.column_label(c))
+                .collect()
+        };
 
-    width as f64 * (step / range)
+        Legend {
+            position: plot.config.legend,
+            labels,
+        }
+    }
+
+    fn entry_width(label: &str) -> f64 {
+        let text_width = label.chars().count() as f64 * FONT_SIZE * CHAR_WIDTH;
+        SWATCH_WIDTH + LEGEND_PAD + text_width.ceil()
+    }
+
+    /// Offsets of each entry from the top left of the legend. Entries run across
+    /// in a row below the plot, and down in a column elsewhere.
+    fn entry_offsets(&self) -> Vec<(f64, f64)> {
+        let mut x = LEGEND_PAD;
+        self.labels
+            .iter()
+            .enumerate()
+            .map(|(i, label)| match self.position {
+                LegendPosition::Bottom => {
+                    let offset = (x, LEGEND_PAD);
+                    x += Self::entry_width(label) + FONT_SIZE;
+                    offset
+                }
+                _ => (LEGEND_PAD, LEGEND_PAD + i as f64 * LEGEND_ROW),
+            })
+            .collect()
+    }
+
+    /// The width and height of the legend, including its padding.
+    fn size(&self) -> (f64, f64) {
+        if self.labels.is_empty() || self.position == LegendPosition::None {
+            return (0.0, 0.0);
+        }
+
+        let widths = self.labels.iter().map(|label| Self::entry_width(label));
+        match self.position {
+            LegendPosition::Bottom => {
+                let gaps = (self.labels.len() - 1) as f64 * FONT_SIZE;
+                (
+                    widths.sum::<f64>() + gaps + 2.0 * LEGEND_PAD,
+                    LEGEND_ROW + 2.0 * LEGEND_PAD,
+                )
+            }
+            _ => (
+                widths.fold(0.0, f64::max) + 2.0 * LEGEND_PAD,
+                self.labels.len() as f64 * LEGEND_ROW + 2.0 * LEGEND_PAD,
+            ),
+        }
+    }
+}
+
+/// Each column's colour and marker alongside its name.
+fn print_legend(
+    plot: &Plot,
+    layout: &Layout,
+    theme: &Theme,
+    out: &mut dyn Write,
+) -> std::io::Result<()> {
+    let Some((x, y)) = layout.legend_origin else {
+        return Ok(());
+    };
+    let legend = &layout.legend;
+    let (width, height) = legend.size();
+
+    writeln!(out, r#"<g transform="translate({},{})">"#, x, y)?;
+    if legend.position != LegendPosition::Bottom {
+        // Keeps the legend readable when it sits over the data.
+        writeln!(
+            out,
+            r#"<rect x="0" y="0" width="{}" height="{}" fill="{}" stroke="{}" stroke-width="1"/>"#,
+            width,
+            height,
+            hex(theme.background),
+            hex(theme.axis)
+        )?;
+    }
+
+    for (c, (label, (ex, ey))) in legend.labels.iter().zip(legend.entry_offsets()).enumerate() {
+        let color = hex(theme.series_color(c));
+        let cy = ey + LEGEND_ROW / 2.0;
+        if plot.config.mode.is_bars() {
+            writeln!(
+                out,
+                r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                ex,
+                cy - FONT_SIZE / 2.0,
+                SWATCH_WIDTH,
+                FONT_SIZE,
+                color
+            )?;
+        } else if plot.config.mode.is_areas() {
+            writeln!(
+                out,
+                r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}" fill-opacity="{}" stroke="{}"/>"#,
+                ex,
+                cy - FONT_SIZE / 2.0,
+                SWATCH_WIDTH,
+                FONT_SIZE,
+                color,
+                AREA_OPACITY,
+                color
+            )?;
+        } else {
+            writeln!(
+                out,
+                r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="{}" />"#,
+                ex,
+                cy,
+                ex + SWATCH_WIDTH,
+                cy,
+                color,
+                theme.line_width
+            )?;
+        }
+        let filled = plot.config.mode.is_bars() || plot.config.mode.is_areas();
+        if !(plot.config.mode == PlotType::Line || filled) {
+            let (x, y) = ((ex + SWATCH_WIDTH / 2.0) as i32, cy as i32);
+            print_marker(x, y, theme.marker_size, theme, &color, out)?;
+        }
+        writeln!(
+            out,
+            r#"<text x="{}" y="{}" fill="{}" font-family="monospace" font-size="{}">{}</text>"#,
+            ex + SWATCH_WIDTH + LEGEND_PAD,
+            cy + FONT_SIZE / 3.0,
+            color,
+            FONT_SIZE,
+            escape_xml(label)
+        )?;
+    }
+
+    writeln!(out, "</g>")
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn end_svg(out: &mut dyn Write) -> std::io::Result<()> {
+    writeln!(out, "</svg>")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn monotone_curves() {
+        let run = [
+            ExactPoint(0.0, 0.0),
+            ExactPoint(1.0, 1.0),
+            ExactPoint(2.0, 1.0),
+            ExactPoint(4.0, 5.0),
+        ];
+        // Flat at the step between equal values, and never steeper than three
+        // times the secant either side.
+        assert_eq!(monotone_tangents(&run), [1.0, 0.0, 0.0, 2.0]);
+        let rising = [
+            ExactPoint(0.0, 0.0),
+            ExactPoint(1.0, 1.0),
+            ExactPoint(3.0, 5.0),
+        ];
+        // Weighted towards the slope of the longer segment.
+        assert_eq!(monotone_tangents(&rising), [1.0, 9.0 / 7.0, 2.0]);
+
+        let controls = curve_controls(&rising, Interpolation::Monotone);
+        // Control points stay between the ends of each segment.
+        for ((c1, c2), pair) in controls.iter().zip(rising.windows(2)) {
+            for c in [c1, c2] {
+                assert!(c.1 >= pair[0].1 && c.1 <= pair[1].1);
+            }
+        }
+    }
+
+    #[test]
+    fn catmull_rom_curves() {
+        let run = [
+            ExactPoint(0.0, 0.0),
+            ExactPoint(3.0, 6.0),
+            ExactPoint(6.0, 0.0),
+        ];
+        let controls = curve_controls(&run, Interpolation::CatmullRom);
+        assert_eq!(
+            controls,
+            [
+                (ExactPoint(0.5, 1.0), ExactPoint(2.0, 6.0)),
+                (ExactPoint(4.0, 6.0), ExactPoint(5.5, 1.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn coords() {
+        assert_eq!(coord(1.23456), 1.23);
+        assert_eq!(coord(-0.001).to_string(), "0");
+        assert_eq!(coord(12.0).to_string(), "12");
+    }
+
+    #[test]
+    fn regression_line_width() {
+        let dataset = crate::types::DataSet::from_columns(vec![vec![
+            Point(0.0, 1.0),
+            Point(1.0, 3.0),
+            Point(2.0, 4.0),
+        ]]);
+        let theme = Theme {
+            line_width: 5.0,
+            ..Theme::default()
+        };
+        let plot = Plot::builder(&dataset)
+            .dimensions(200, 100)
+            .theme(theme)
+            .build()
+            .unwrap();
+
+        let mut out = Vec::new();
+        SvgPlot.render(&plot, &mut out).unwrap();
+        let svg = String::from_utf8(out).unwrap();
+        let line = svg
+            .lines()
+            .find(|line| line.contains("stroke-dasharray=\"5\""));
+        assert!(line.unwrap().contains("stroke-width=\"5\""));
+    }
 }
@@ -1,111 +1,152 @@
+use std::collections::HashSet;
+use std::io::Write;
+
 use crate::config::PlotType;
-use crate::draw::Plot;
+use crate::draw::{Plot, Renderer};
 use crate::scale::{ScaledPoint, TransformType};
-use colored::Colorize;
+use crate::theme::Rgb;
+use crate::ticks::Tick;
 
-pub struct AsciiPlot<'a> {
-    plot: &'a Plot<'a>,
-    rows: Vec<Vec<String>>,
-}
+use super::{
+    area_columns, col_mark, label_row, line_path, line_runs, print_header, shade, x_ticks, y_ticks,
+};
 
-impl<'a> AsciiPlot<'a> {
-    pub fn new(plot: &'a Plot<'a>) -> Self {
-        let s = String::from(" ");
-        let row = vec![s; plot.width()];
-        let mut rows = Vec::new();
-        for _ in 0..plot.height() {
-            rows.push(row.clone());
+/// Renders a plot as a grid of ASCII marks, one character per cell.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AsciiPlot;
+
+impl Renderer for AsciiPlot {
+    fn render(&self, plot: &Plot, out: &mut dyn Write) -> std::io::Result<()> {
+        let mut graph = AsciiCanvas::new(plot);
+        if plot.config.axis {
+            graph.draw_axes();
         }
 
-        Self { plot, rows }
+        let mut heat_max = None;
+        match plot.config.mode {
+            PlotType::Line => graph.plot_lines(),
+            PlotType::Bar | PlotType::Histogram => graph.plot_bars(),
+            PlotType::Box => graph.plot_boxes(),
+            PlotType::Heatmap => heat_max = Some(graph.plot_heatmap()),
+            PlotType::Area | PlotType::StackedArea => graph.plot_areas(),
+            PlotType::Dot | PlotType::Count => graph.plot_points(),
+        }
+
+        print_header(plot, heat_max, out)?;
+        graph.print_graph(out)?;
+
+        Ok(())
     }
+}
 
-    fn print_header(&mut self) {
-        let point_counts = self.plot.config.mode == PlotType::Count;
-        let columns = self.plot.dataset.columns;
-        if self.plot.config.log_x {
-            print!(
-                "    x: log [{} - {}]",
-                self.plot.x_min().exp(),
-                self.plot.x_max().exp()
-            );
+struct AsciiCanvas<'a> {
+    plot: &'a Plot<'a>,
+    width: usize,
+    height: usize,
+    /// Width of the Y tick labels to the left of the plot, with their padding.
+    margin: usize,
+    x_ticks: Vec<(usize, Tick)>,
+    y_ticks: Vec<(usize, Tick)>,
+    rows: Vec<Vec<String>>,
+}
+
+impl<'a> AsciiCanvas<'a> {
+    /// Lay out the plot so that it fits in its dimensions along with the tick
+    /// labels, which take a row below it and a margin to its left.
+    fn new(plot: &'a Plot<'a>) -> Self {
+        let axis = plot.config.axis;
+        let height = plot.height().saturating_sub(usize::from(axis)).max(1);
+        let y_ticks = if axis {
+            y_ticks(plot, height, 1)
         } else {
-            print!("    x: [{} - {}]", self.plot.x_min(), self.plot.x_max());
-        }
+            Vec::new()
+        };
 
-        if self.plot.log_y() {
-            print!(
-                "    y: log [{} - {}]",
-                self.plot.y_min().exp(),
-                self.plot.y_max().exp()
-            );
+        let margin = y_ticks
+            .iter()
+            .map(|(_, tick)| tick.label.chars().count() + 1)
+            .max()
+            .unwrap_or(0);
+        let width = plot.width().saturating_sub(margin).max(1);
+        let x_ticks = if axis {
+            x_ticks(plot, width, 1)
         } else {
-            print!("    y: [{} - {}]", self.plot.y_min(), self.plot.y_max());
+            Vec::new()
+        };
+
+        let s = String::from(" ");
+        let row = vec![s; width];
+        let mut rows = Vec::new();
+        for _ in 0..height {
+            rows.push(row.clone());
         }
 
-        if !point_counts {
-            print!(" -- ");
-            let count_key = (0..columns)
-                .map(|i| {
-                    let (r, g, b) = self.plot.config.color_scheme.series_color(i);
-                    col_mark(i).to_string().truecolor(r, g, b).to_string()
-                })
-                .collect::<Vec<_>>()
-                .join(", ");
-            print!("{}", count_key);
+        Self {
+            plot,
+            width,
+            height,
+            margin,
+            x_ticks,
+            y_ticks,
+            rows,
         }
-        println!();
     }
 
     fn draw_axes(&mut self) {
-        let (x_axis, y_axis) = self.plot.axis_positions();
+        let (x_axis, y_axis) = self.plot.scaled_axis_positions(self.width, self.height);
+        let is_x_tick = |i: usize| self.x_ticks.iter().any(|(col, _)| *col == i);
+        let is_y_tick = |i: usize| self.y_ticks.iter().any(|(row, _)| *row == i);
 
-        let (r, g, b) = self.plot.config.color_scheme.axis_color();
+        let color = self.plot.config.theme.axis_color();
+        let depth = self.plot.config.color_depth;
         for (i, row) in self.rows.iter_mut().enumerate() {
             let c = if self.plot.draw_y_axis() {
-                if i % 5 == 0 {
+                if is_y_tick(i) {
                     "+"
                 } else {
                     "|"
                 }
-            } else if i % 5 == 0 {
+            } else if is_y_tick(i) {
                 "."
             } else {
                 " "
             };
-            row[x_axis] = c.truecolor(r, g, b).to_string();
+            row[x_axis] = depth.paint(c, color);
         }
 
-        for i in 0..self.plot.width() {
+        for i in 0..self.width {
             let c = if self.plot.draw_x_axis() {
-                if i % 5 == 0 {
+                if is_x_tick(i) {
                     "+"
                 } else {
                     "─"
                 }
-            } else if i % 5 == 0 {
+            } else if is_x_tick(i) {
                 "."
             } else {
                 " "
             };
-            self.rows[y_axis][i] = c.truecolor(r, g, b).to_string();
+            self.rows[y_axis][i] = depth.paint(c, color);
         }
 
-        self.rows[y_axis][x_axis] = "+".truecolor(r, g, b).to_string();
+        self.rows[y_axis][x_axis] = depth.paint("+", color);
     }
 
     fn plot_points(&mut self) {
+        let transform = TransformType::new(self.plot.log_x(), self.plot.log_y());
+        let bounds = self.plot.bounds(self.width, self.height);
+        let counters = (self.plot.config.mode == PlotType::Count)
+            .then(|| self.plot.scaled_counters(self.width, self.height));
+
         for c in 0..self.plot.dataset.columns {
             for r in 0..self.plot.dataset.rows {
                 let p = self.plot.dataset.points[c][r];
                 if p.is_empty() {
                     continue;
                 }
-                let transform = TransformType::new(self.plot.log_x(), self.plot.log_y());
-                let sp = ScaledPoint::new_from_plot(p, self.plot, transform);
+                let sp = ScaledPoint::new_from_bounds(p, transform, bounds.clone());
                 let mut mark = col_mark(c);
-                if let PlotType::Count = self.plot.config.mode {
-                    let counters = &mut self.plot.counters();
+                if let Some(counters) = &counters {
                     let count = counters.counters[c].get(&(sp.0, sp.1));
                     if let Some(&count) = count {
                         if count < 10 {
@@ -119,42 +160,280 @@ impl<'a> AsciiPlot<'a> {
                     }
                 }
 
-                let (r, g, b) = self.plot.config.color_scheme.series_color(c);
-                let mark = mark.to_string().truecolor(r, g, b).to_string();
+                let color = self.plot.config.theme.series_color(c);
+                self.set_cell(sp.0, sp.1, &mark.to_string(), color);
+            }
+        }
+    }
+
+    /// Join up the points of each column with box drawing characters, each
+    /// following the line through its neighbours on the path.
+    fn plot_lines(&mut self) {
+        let bounds = self.plot.bounds(self.width, self.height);
+        for c in 0..self.plot.dataset.columns {
+            let color = self.plot.config.theme.series_color(c);
+            for run in line_runs(self.plot, c, &bounds) {
+                let path = line_path(&run);
+                if let [(x, y)] = path[..] {
+                    self.set_cell(x, y, &col_mark(c).to_string(), color);
+                    continue;
+                }
+
+                for (i, &(x, y)) in path.iter().enumerate() {
+                    let prev = path[i.saturating_sub(1)];
+                    let next = path.get(i + 1).copied().unwrap_or((x, y));
+                    self.set_cell(x, y, line_glyph(prev, (x, y), next), color);
+                }
+            }
+        }
+    }
+
+    /// Draw each bar as a column of blocks, ending in a partial block to show
+    /// where its value falls within the last cell.
+    fn plot_bars(&mut self) {
+        let bounds = self.plot.bounds(self.width, self.height);
+        // Measure from the bottom of the axis row, so that bars of the same
+        // value are the same height wherever the axis falls within its cell.
+        let baseline = bounds.y_level(self.plot.baseline());
+        let level = |y| bounds.y_level(y) - baseline.fract();
+        for bar in self.plot.bars() {
+            let color = self.plot.config.theme.series_color(bar.column);
+            let left = bounds.x_level(bar.left) as i32;
+            let right = (bounds.x_level(bar.right) as i32).max(left + 1);
+            let cells = bar_cells(level(bar.base), level(bar.value));
+            for x in left..right {
+                for &(level, block) in &cells {
+                    self.set_cell(x, self.height as i32 - 1 - level, block, color);
+                }
+            }
+        }
+    }
+
+    /// Fill each area with blocks, like a bar in every column of cells it
+    /// covers.
+    fn plot_areas(&mut self) {
+        let bounds = self.plot.bounds(self.width, self.height);
+        // Measured from the bottom of the axis row, as for bars.
+        let offset = bounds.y_level(self.plot.baseline()).fract();
+        // Overlapping areas would leave holes where the partial block at the
+        // top of one goes over a full block of another.
+        let mut full = HashSet::new();
+        for area in self.plot.areas() {
+            let color = self.plot.config.theme.series_color(area.column);
+            for (x, base, value) in area_columns(&area, &bounds) {
+                for (level, block) in bar_cells(base - offset, value - offset) {
+                    let y = self.height as i32 - 1 - level;
+                    if block == "█" {
+                        full.insert((x, y));
+                    } else if full.contains(&(x, y)) {
+                        continue;
+                    }
+                    self.set_cell(x, y, block, color);
+                }
+            }
+        }
+    }
+
+    /// Draw a box and whiskers for each column, centred on its tick, with a
+    /// heavy line across the box at the median.
+    fn plot_boxes(&mut self) {
+        let bounds = self.plot.bounds(self.width, self.height);
+        let height = self.height as i32;
+        let row = |y: f64| height - 1 - bounds.y_level(y) as i32;
+        // Leave at least as much room between boxes as they take up.
+        let columns = self.plot.dataset.columns.max(1);
+        let half = (self.width / columns / 4).clamp(1, 4) as i32;
+
+        for (col, stats) in self.plot.boxes() {
+            let color = self.plot.config.theme.series_color(col);
+            let x = bounds.x_level(col as f64) as i32;
+            let (max, q3, median, q1, min) = (
+                row(stats.max),
+                row(stats.q3),
+                row(stats.median),
+                row(stats.q1),
+                row(stats.min),
+            );
 
-                self.rows[sp.1 as usize][sp.0 as usize] = mark.to_string();
+            for y in (max + 1..q3).chain(q1 + 1..min) {
+                self.set_cell(x, y, "│", color);
+            }
+            for dx in -1..=1 {
+                self.set_cell(x + dx, max, if dx == 0 { "┬" } else { "─" }, color);
+                self.set_cell(x + dx, min, if dx == 0 { "┴" } else { "─" }, color);
+            }
+
+            for y in q3..=q1 {
+                self.set_cell(x - half, y, "│", color);
+                self.set_cell(x + half, y, "│", color);
+            }
+            for dx in 1 - half..half {
+                self.set_cell(x + dx, q3, "─", color);
+                self.set_cell(x + dx, q1, "─", color);
+            }
+            self.set_cell(x - half, q3, "┌", color);
+            self.set_cell(x + half, q3, "┐", color);
+            self.set_cell(x - half, q1, "└", color);
+            self.set_cell(x + half, q1, "┘", color);
+            if max < q3 {
+                self.set_cell(x, q3, "┴", color);
+            }
+            if min > q1 {
+                self.set_cell(x, q1, "┬", color);
+            }
+
+            for dx in 1 - half..half {
+                self.set_cell(x + dx, median, "━", color);
+            }
+            self.set_cell(x - half, median, "┝", color);
+            self.set_cell(x + half, median, "┥", color);
+
+            for &outlier in &stats.outliers {
+                self.set_cell(x, row(outlier), "o", color);
             }
         }
     }
 
-    pub fn print_graph(&self) {
-        for row in &self.rows {
+    /// Shade each cell holding points by how many it holds, lightest for the
+    /// fewest, and return the most in any cell.
+    fn plot_heatmap(&mut self) -> u32 {
+        let density = self.plot.density(self.width, self.height);
+        let max = density.values().copied().max().unwrap_or(1);
+        let theme = &self.plot.config.theme;
+        for (&(x, y), &count) in &density {
+            let heat = self.plot.heat(count, max);
+            let color = self.plot.config.colormap.color(heat, theme);
+            self.set_cell(x, y, shade(heat), color);
+        }
+        max
+    }
+
+    fn set_cell(&mut self, x: i32, y: i32, mark: &str, color: Rgb) {
+        if x < 0 || y < 0 {
+            return;
+        }
+        let depth = self.plot.config.color_depth;
+        if let Some(cell) = self
+            .rows
+            .get_mut(y as usize)
+            .and_then(|row| row.get_mut(x as usize))
+        {
+            *cell = depth.paint(mark, color);
+        }
+    }
+
+    fn print_graph(&self, out: &mut dyn Write) -> std::io::Result<()> {
+        for (i, row) in self.rows.iter().enumerate() {
+            if self.margin > 0 {
+                let label = self
+                    .y_ticks
+                    .iter()
+                    .find(|(row, _)| *row == i)
+                    .map_or("", |(_, tick)| tick.label.as_str());
+                write!(out, "{:>1$} ", label, self.margin - 1)?;
+            }
             for col in row {
-                print!("{}", col);
+                write!(out, "{}", col)?;
             }
-            println!();
+            writeln!(out)?;
+        }
+
+        if self.plot.config.axis {
+            writeln!(
+                out,
+                "{}{}",
+                " ".repeat(self.margin),
+                label_row(&self.x_ticks, 1, self.width)
+            )?;
         }
+
+        Ok(())
     }
 }
 
-const COL_MARKS: &[u8; 33] = b"#@*^!~%ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+/// The box drawing character for a cell on a line, from the cells before and
+/// after it, with y counting down. A line turning from across to up or down,
+/// as at the corners of steps, gets a corner. Otherwise the character is the
+/// one nearest the direction from the cell before to the cell after.
+fn line_glyph(prev: (i32, i32), (x, y): (i32, i32), next: (i32, i32)) -> &'static str {
+    let sides = [prev, next].map(|(nx, ny)| (nx - x, ny - y));
+    let across = sides
+        .iter()
+        .find(|&&side| side == (-1, 0) || side == (1, 0));
+    let upright = sides
+        .iter()
+        .find(|&&side| side == (0, -1) || side == (0, 1));
+    if let (Some(&(dx, _)), Some(&(_, dy))) = (across, upright) {
+        return match (dx, dy) {
+            (1, -1) => "└",
+            (-1, -1) => "┘",
+            (1, _) => "┌",
+            _ => "┐",
+        };
+    }
 
-fn col_mark(col: usize) -> char {
-    if col < COL_MARKS.len() {
-        COL_MARKS[col] as char
+    let ((x0, y0), (x1, y1)) = (prev, next);
+    let (dx, dy) = (x1 - x0, y1 - y0);
+    if dx.abs() >= 2 * dy.abs() {
+        "─"
+    } else if dy.abs() >= 2 * dx.abs() {
+        "│"
+    } else if (dx > 0) == (dy < 0) {
+        "╱"
     } else {
-        '*'
+        "╲"
     }
 }
 
-pub fn ascii_plot(plot: &Plot) {
-    let mut graph = AsciiPlot::new(plot);
-    if plot.config.axis {
-        graph.draw_axes();
+/// Blocks filling the bottom eighths of a cell, from one eighth to all of it.
+const BLOCKS: [&str; 8] = ["▁", "▂", "▃", "▄", "▅", "▆", "▇", "█"];
+
+/// The cells of a bar between two levels, counting up from the bottom, each
+/// with the block to draw in it. Bars going up end in a partial block. There are
+/// no blocks which fill the top eighths of a cell, so bars going down end in a
+/// full or half block.
+fn bar_cells(base: f64, value: f64) -> Vec<(i32, &'static str)> {
+    let mut cells = Vec::new();
+    if value > base {
+        for level in base.floor() as i32..value.ceil() as i32 {
+            let eighths = ((value - level as f64).min(1.0) * 8.0).round() as usize;
+            if eighths > 0 {
+                cells.push((level, BLOCKS[eighths - 1]));
+            }
+        }
+    } else if value < base {
+        for level in value.floor() as i32..=base.floor() as i32 {
+            let fill = (level as f64 + 1.0 - value).min(1.0);
+            if fill >= 0.75 {
+                cells.push((level, "█"));
+            } else if fill >= 0.25 {
+                cells.push((level, "▀"));
+            }
+        }
     }
 
-    graph.print_header();
-    graph.plot_points();
+    cells
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
 
-    graph.print_graph();
+    #[test]
+    fn bar_blocks() {
+        assert_eq!(bar_cells(0.0, 2.5), [(0, "█"), (1, "█"), (2, "▄")]);
+        assert_eq!(bar_cells(0.5, 1.25), [(0, "█"), (1, "▂")]);
+        assert_eq!(bar_cells(3.5, 1.5), [(1, "▀"), (2, "█"), (3, "█")]);
+        assert_eq!(bar_cells(1.0, 1.0), []);
+    }
+
+    #[test]
+    fn line_glyphs() {
+        assert_eq!(line_glyph((0, 0), (1, 0), (2, 0)), "─");
+        assert_eq!(line_glyph((0, 2), (1, 1), (2, 0)), "╱");
+        assert_eq!(line_glyph((0, 0), (1, 0), (1, 1)), "┐");
+        assert_eq!(line_glyph((1, 1), (1, 0), (2, 0)), "┌");
+        assert_eq!(line_glyph((0, 1), (1, 1), (1, 0)), "┘");
+        assert_eq!(line_glyph((1, 0), (1, 1), (2, 1)), "└");
+    }
 }
@@ -1,24 +1,296 @@
+use std::io::Write;
 
-use crate::draw::Plot;
+use crate::color::ColorDepth;
+use crate::config::PlotType;
+use crate::draw::{Plot, Renderer};
+use crate::scale::{ScaledPoint, TransformType};
 
+use super::braille_point::BraillePoint;
+use super::{area_columns, label_row, line_path, line_runs, print_header, x_label_rows, x_ticks};
 
+/// Each braille character holds a 2x4 matrix of dots.
+const DOTS_X: usize = 2;
+const DOTS_Y: usize = 4;
 
-pub fn braille_plot(_plot: &Plot) {
-    todo!()
+#[derive(Debug, Clone, Copy, Default)]
+struct Cell {
+    point: BraillePoint,
+    color: Option<(u8, u8, u8)>,
 }
 
-fn get_rows(_plot: &Plot) -> Vec<Vec<String>> {
-    todo!()
+/// Renders a plot using braille characters, packing 2x4 plot cells into
+/// each character.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BraillePlot;
+
+impl Renderer for BraillePlot {
+    fn render(&self, plot: &Plot, out: &mut dyn Write) -> std::io::Result<()> {
+        let mut rows = get_rows(plot);
+        if plot.config.axis {
+            draw_axes(plot, &mut rows);
+        }
+
+        let mut heat_max = None;
+        match plot.config.mode {
+            PlotType::Line => plot_lines(plot, &mut rows),
+            PlotType::Bar | PlotType::Histogram => plot_bars(plot, &mut rows),
+            PlotType::Box => plot_boxes(plot, &mut rows),
+            PlotType::Heatmap => heat_max = Some(plot_heatmap(plot, &mut rows)),
+            PlotType::Area | PlotType::StackedArea => plot_areas(plot, &mut rows),
+            PlotType::Dot | PlotType::Count => plot_points(plot, &mut rows),
+        }
+
+        print_header(plot, heat_max, out)?;
+        print_graph(&rows, plot.config.color_depth, out)?;
+
+        if x_label_rows(plot) > 0 {
+            let ticks = x_ticks(plot, plot.width() * DOTS_X, DOTS_X);
+            writeln!(out, "{}", label_row(&ticks, DOTS_X, plot.width()))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn get_rows(plot: &Plot) -> Vec<Vec<Cell>> {
+    let height = plot.height().saturating_sub(x_label_rows(plot)).max(1);
+    vec![vec![Cell::default(); plot.width()]; height]
+}
+
+/// The size of the chart in dots.
+fn dot_size(rows: &[Vec<Cell>]) -> (usize, usize) {
+    let width = rows.first().map_or(0, |row| row.len());
+    (width * DOTS_X, rows.len() * DOTS_Y)
+}
+
+/// Set a single dot, where x and y are dot coordinates starting from the top left
+/// of the chart. The colour of the whole cell is replaced, as a braille character
+/// can only be drawn in one colour.
+fn set_dot(rows: &mut [Vec<Cell>], x: usize, y: usize, color: (u8, u8, u8)) {
+    let Some(cell) = rows
+        .get_mut(y / DOTS_Y)
+        .and_then(|row| row.get_mut(x / DOTS_X))
+    else {
+        return;
+    };
+
+    // BraillePoint counts y from the bottom of the cell.
+    cell.point.set(x % DOTS_X, DOTS_Y - 1 - y % DOTS_Y);
+    cell.color = Some(color);
+}
+
+fn draw_axes(plot: &Plot, rows: &mut [Vec<Cell>]) {
+    let (dots_w, dots_h) = dot_size(rows);
+    let (x_axis, y_axis) = plot.scaled_axis_positions(dots_w, dots_h);
+    let color = plot.config.theme.axis_color();
+
+    // Ticks every 5 characters, drawn next to the axis line.
+    let labelled = plot.time_x() || plot.config.mode == PlotType::Box;
+    let x_ticks: Option<Vec<usize>> = labelled.then(|| {
+        x_ticks(plot, dots_w, DOTS_X)
+            .into_iter()
+            .map(|(x, _)| x)
+            .collect()
+    });
+    let tick_x = if x_axis + 1 < dots_w {
+        x_axis + 1
+    } else {
+        x_axis.saturating_sub(1)
+    };
+    let tick_y = y_axis.saturating_sub(1);
+
+    for y in 0..dots_h {
+        let tick = y % (DOTS_Y * 5) == 0;
+        if plot.draw_y_axis() {
+            set_dot(rows, x_axis, y, color);
+            if tick {
+                set_dot(rows, tick_x, y, color);
+            }
+        } else if tick {
+            set_dot(rows, x_axis, y, color);
+        }
+    }
+
+    for x in 0..dots_w {
+        let tick = match &x_ticks {
+            Some(ticks) => ticks.contains(&x),
+            None => x % (DOTS_X * 5) == 0,
+        };
+        if plot.draw_x_axis() {
+            set_dot(rows, x, y_axis, color);
+            if tick {
+                set_dot(rows, x, tick_y, color);
+            }
+        } else if tick {
+            set_dot(rows, x, y_axis, color);
+        }
+    }
+}
+
+fn plot_points(plot: &Plot, rows: &mut [Vec<Cell>]) {
+    let (dots_w, dots_h) = dot_size(rows);
+    let bounds = plot.bounds(dots_w, dots_h);
+    let transform = TransformType::new(plot.log_x(), plot.log_y());
+
+    for c in 0..plot.dataset.columns {
+        let color = plot.config.theme.series_color(c);
+        for p in plot.dataset.points[c].iter().take(plot.dataset.rows) {
+            if p.is_empty() {
+                continue;
+            }
+
+            let sp = ScaledPoint::new_from_bounds(*p, transform, bounds.clone());
+            if sp.0 < 0 || sp.1 < 0 {
+                continue;
+            }
+
+            set_dot(rows, sp.0 as usize, sp.1 as usize, color);
+        }
+    }
+}
+
+/// Fill in the dots covered by each bar.
+fn plot_bars(plot: &Plot, rows: &mut [Vec<Cell>]) {
+    let (dots_w, dots_h) = dot_size(rows);
+    let bounds = plot.bounds(dots_w, dots_h);
+
+    for bar in plot.bars() {
+        if bar.value == bar.base {
+            continue;
+        }
+
+        let color = plot.config.theme.series_color(bar.column);
+        let left = bounds.x_level(bar.left) as usize;
+        let right = (bounds.x_level(bar.right) as usize).max(left + 1);
+        // Dot rows count down from the top.
+        let row = |y| dots_h.saturating_sub(bounds.y_level(y) as usize + 1);
+        let (base, value) = (row(bar.base), row(bar.value));
+        for x in left..right {
+            for y in base.min(value)..=base.max(value) {
+                set_dot(rows, x, y, color);
+            }
+        }
+    }
+}
+
+/// Fill in the dots covered by each area, down to its base.
+fn plot_areas(plot: &Plot, rows: &mut [Vec<Cell>]) {
+    let (dots_w, dots_h) = dot_size(rows);
+    let bounds = plot.bounds(dots_w, dots_h);
+    // Dot rows count down from the top.
+    let row = |level: f64| dots_h.saturating_sub(level as usize + 1);
+
+    for area in plot.areas() {
+        let color = plot.config.theme.series_color(area.column);
+        for (x, base, value) in area_columns(&area, &bounds) {
+            if x < 0 {
+                continue;
+            }
+            let (base, value) = (row(base), row(value));
+            for y in base.min(value)..=base.max(value) {
+                set_dot(rows, x as usize, y, color);
+            }
+        }
+    }
 }
 
-fn draw_axes(_plot: &Plot, _rows: &mut [Vec<String>]) {
-    todo!()
+/// Outline a box and whiskers for each column in dots.
+fn plot_boxes(plot: &Plot, rows: &mut [Vec<Cell>]) {
+    let (dots_w, dots_h) = dot_size(rows);
+    let bounds = plot.bounds(dots_w, dots_h);
+    let row = |y: f64| dots_h.saturating_sub(bounds.y_level(y) as usize + 1);
+    let columns = plot.dataset.columns.max(1);
+    let half = (dots_w / columns / 4).clamp(1, 8);
+
+    for (col, stats) in plot.boxes() {
+        let color = plot.config.theme.series_color(col);
+        let x = bounds.x_level(col as f64) as usize;
+        let (left, right) = (x.saturating_sub(half), x + half);
+        let (max, q3, median, q1, min) = (
+            row(stats.max),
+            row(stats.q3),
+            row(stats.median),
+            row(stats.q1),
+            row(stats.min),
+        );
+
+        for y in (max..q3).chain(q1..=min) {
+            set_dot(rows, x, y, color);
+        }
+        for dx in x.saturating_sub(half / 2)..=x + half / 2 {
+            set_dot(rows, dx, max, color);
+            set_dot(rows, dx, min, color);
+        }
+        for y in q3..=q1 {
+            set_dot(rows, left, y, color);
+            set_dot(rows, right, y, color);
+        }
+        for dx in left..=right {
+            set_dot(rows, dx, q3, color);
+            set_dot(rows, dx, median, color);
+            set_dot(rows, dx, q1, color);
+        }
+        for &outlier in &stats.outliers {
+            set_dot(rows, x, row(outlier), color);
+        }
+    }
 }
 
-fn print_header(_plot: &Plot, _count_mode: bool, _columns: usize) {
-    todo!()
+/// Fill each character holding points with dots from the bottom up, more of
+/// them and further along the colour map the more points it holds, and return
+/// the most in any character.
+fn plot_heatmap(plot: &Plot, rows: &mut [Vec<Cell>]) -> u32 {
+    let width = rows.first().map_or(0, |row| row.len());
+    let density = plot.density(width, rows.len());
+    let max = density.values().copied().max().unwrap_or(1);
+    let dots = DOTS_X * DOTS_Y;
+
+    for (&(x, y), &count) in &density {
+        if x < 0 || y < 0 {
+            continue;
+        }
+        let heat = plot.heat(count, max);
+        let color = plot.config.colormap.color(heat, &plot.config.theme);
+        let filled = ((heat * dots as f64).ceil() as usize).clamp(1, dots);
+        for i in 0..filled {
+            let dot_x = x as usize * DOTS_X + i % DOTS_X;
+            let dot_y = (y as usize + 1) * DOTS_Y - 1 - i / DOTS_X;
+            set_dot(rows, dot_x, dot_y, color);
+        }
+    }
+    max
 }
 
-fn plot_points(_plot: &Plot, _rows: &mut [Vec<String>]) {
-    todo!()
+/// Join up the points of each column with lines of dots.
+fn plot_lines(plot: &Plot, rows: &mut [Vec<Cell>]) {
+    let (dots_w, dots_h) = dot_size(rows);
+    let bounds = plot.bounds(dots_w, dots_h);
+
+    for c in 0..plot.dataset.columns {
+        let color = plot.config.theme.series_color(c);
+        for run in line_runs(plot, c, &bounds) {
+            for (x, y) in line_path(&run) {
+                if x >= 0 && y >= 0 {
+                    set_dot(rows, x as usize, y as usize, color);
+                }
+            }
+        }
+    }
+}
+
+fn print_graph(rows: &[Vec<Cell>], depth: ColorDepth, out: &mut dyn Write) -> std::io::Result<()> {
+    for row in rows {
+        for cell in row {
+            match cell.color {
+                Some(color) => {
+                    let c = char::from(cell.point).to_string();
+                    write!(out, "{}", depth.paint(&c, color))?;
+                }
+                None => write!(out, " ")?,
+            }
+        }
+        writeln!(out)?;
+    }
+
+    Ok(())
 }
@@ -12,10 +12,9 @@
 /// 0001 0011
 ///
 /// This can then be added to 0x2800 to get the Unicode code point.
-
+///
 /// A struct containing a 2x4 matrix of chart points,
 /// which can be represented by a single braille character.
-
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub struct BraillePoint {
     value: u8,
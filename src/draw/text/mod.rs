@@ -1,3 +1,11 @@
+use std::io::Write;
+
+use crate::config::PlotType;
+use crate::draw::{interpolate, Area, Plot};
+use crate::scale::{Bounds, ScaledPoint, TransformType};
+use crate::ticks::{value_ticks, Tick};
+use crate::types::Point;
+
 pub mod ascii;
 pub mod braille;
 mod braille_point;
@@ -36,3 +44,323 @@ mod braille_point;
 //   be mapped to a single braille character.
 //
 //
+
+/// Print the bounds of the plot, followed by a key of the marks and colours
+/// used for each column, or of the shades of a heatmap holding up to `heat_max`
+/// points in a cell. Shared by all text renderers.
+pub fn print_header(
+    plot: &Plot,
+    heat_max: Option<u32>,
+    out: &mut dyn Write,
+) -> std::io::Result<()> {
+    let point_counts = plot.config.mode == PlotType::Count;
+    let columns = plot.dataset.columns;
+    if plot.config.mode == PlotType::Box {
+        // The columns are laid out along the X axis, which has no scale.
+    } else if plot.config.log_x {
+        write!(
+            out,
+            "    x: log [{} - {}]",
+            plot.x_min().exp(),
+            plot.x_max().exp()
+        )?;
+    } else {
+        write!(
+            out,
+            "    x: [{} - {}]",
+            plot.format_x(plot.x_min()),
+            plot.format_x(plot.x_max())
+        )?;
+    }
+
+    if plot.log_y() {
+        write!(
+            out,
+            "    y: log [{} - {}]",
+            plot.y_min().exp(),
+            plot.y_max().exp()
+        )?;
+    } else {
+        write!(out, "    y: [{} - {}]", plot.y_min(), plot.y_max())?;
+    }
+
+    if let Some(max) = heat_max {
+        // The columns are all counted together, so the key is the colour scale.
+        let scale = SHADES
+            .iter()
+            .enumerate()
+            .map(|(i, mark)| {
+                let heat = (i + 1) as f64 / SHADES.len() as f64;
+                let color = plot.config.colormap.color(heat, &plot.config.theme);
+                plot.config.color_depth.paint(mark, color)
+            })
+            .collect::<String>();
+        write!(out, " -- {} 1–{} points per cell", scale, max)?;
+    } else if !point_counts {
+        write!(out, " -- ")?;
+        let count_key = (0..columns)
+            .map(|i| {
+                let color = plot.config.theme.series_color(i);
+                let mark = match plot.config.mode {
+                    PlotType::Bar
+                    | PlotType::Histogram
+                    | PlotType::Area
+                    | PlotType::StackedArea => '█',
+                    _ => col_mark(i),
+                };
+                let mark = plot.config.color_depth.paint(&mark.to_string(), color);
+                match plot.dataset.column_name(i) {
+                    Some(name) => format!("{} {}", mark, name),
+                    None => mark,
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(out, "{}", count_key)?;
+    }
+    writeln!(out)
+}
+
+/// How many rows below the plot are given over to X axis labels.
+fn x_label_rows(plot: &Plot) -> usize {
+    if plot.config.axis && (plot.time_x() || plot.config.mode == PlotType::Box) {
+        1
+    } else {
+        0
+    }
+}
+
+/// Ticks along the X axis of a text plot `width` cells wide, each with the cell
+/// it falls in. `cell_width` is the number of cells in each character.
+fn x_ticks(plot: &Plot, width: usize, cell_width: usize) -> Vec<(usize, Tick)> {
+    // Leave room for labels such as "Oct 17" between the ticks.
+    let max_ticks = width / cell_width / 10;
+    let bounds = plot.bounds(width, 1);
+    let transform = TransformType::new(plot.log_x(), false);
+
+    let ticks = plot.x_tick_values(max_ticks);
+
+    ticks
+        .into_iter()
+        .filter_map(|tick| {
+            let point = Point(tick.value, plot.y_min());
+            let sp = ScaledPoint::new_from_bounds(point, transform, bounds.clone());
+            (sp.0 >= 0 && (sp.0 as usize) < width).then_some((sp.0 as usize, tick))
+        })
+        .collect()
+}
+
+/// Ticks along the Y axis of a text plot `height` cells tall, each with the cell
+/// it falls in, counting from the top. `cell_height` is the number of cells in
+/// each character.
+fn y_ticks(plot: &Plot, height: usize, cell_height: usize) -> Vec<(usize, Tick)> {
+    let max_ticks = height / cell_height / 4;
+    let bounds = plot.bounds(1, height);
+    let transform = TransformType::new(false, plot.log_y());
+
+    value_ticks(plot.y_min(), plot.y_max(), plot.log_y(), max_ticks)
+        .into_iter()
+        .filter_map(|tick| {
+            let point = Point(plot.x_min(), tick.value);
+            let sp = ScaledPoint::new_from_bounds(point, transform, bounds.clone());
+            (sp.1 >= 0 && (sp.1 as usize) < height).then_some((sp.1 as usize, tick))
+        })
+        .collect()
+}
+
+/// The runs of consecutive points in a column, scaled to `bounds`, to be joined
+/// up in line mode, with the corners of any steps between them. Empty values
+/// break the line, as in the SVG renderer.
+fn line_runs(plot: &Plot, column: usize, bounds: &Bounds) -> Vec<Vec<ScaledPoint>> {
+    let transform = TransformType::new(plot.log_x(), plot.log_y());
+    let mut runs = Vec::new();
+    let mut run = Vec::new();
+    for p in plot.dataset.points[column].iter().take(plot.dataset.rows) {
+        if p.is_empty() {
+            if !run.is_empty() {
+                runs.push(std::mem::take(&mut run));
+            }
+            continue;
+        }
+        run.push(ScaledPoint::new_from_bounds(*p, transform, bounds.clone()));
+    }
+    if !run.is_empty() {
+        runs.push(run);
+    }
+
+    runs.iter()
+        .map(|run| interpolate(run, plot.config.interpolate))
+        .collect()
+}
+
+/// Each column of cells an area covers, with the levels it's filled between
+/// there, counting up from the bottom. The levels are taken from the middle of
+/// the cell, on the straight lines between the points of the area.
+fn area_columns(area: &Area, bounds: &Bounds) -> Vec<(i32, f64, f64)> {
+    let levels: Vec<(f64, f64, f64)> = area
+        .points
+        .iter()
+        .map(|p| {
+            (
+                bounds.x_level(p.x),
+                bounds.y_level(p.base),
+                bounds.y_level(p.value),
+            )
+        })
+        .collect();
+    if let [(x, base, value)] = levels[..] {
+        return vec![(x as i32, base, value)];
+    }
+
+    let mut columns = Vec::new();
+    for pair in levels.windows(2) {
+        let ((x0, base0, value0), (x1, base1, value1)) = (pair[0], pair[1]);
+        for x in x0.min(x1) as i32..=x0.max(x1) as i32 {
+            let t = if x1 == x0 {
+                0.0
+            } else {
+                ((x as f64 + 0.5 - x0) / (x1 - x0)).clamp(0.0, 1.0)
+            };
+            columns.push((
+                x,
+                base0 + (base1 - base0) * t,
+                value0 + (value1 - value0) * t,
+            ));
+        }
+    }
+    // Each segment starts in the column where the last ended.
+    columns.dedup_by_key(|column| column.0);
+
+    columns
+}
+
+/// The cells on the lines joining up a run of points, without repeats. A run of
+/// one point is a single cell.
+fn line_path(run: &[ScaledPoint]) -> Vec<(i32, i32)> {
+    let mut path = vec![(run[0].0, run[0].1)];
+    for segment in run.windows(2) {
+        // Each segment starts where the last ended.
+        path.extend(line_cells(segment[0], segment[1]).into_iter().skip(1));
+    }
+    path.dedup();
+
+    path
+}
+
+/// The cells on the straight line between two cells, both included, by
+/// Bresenham's algorithm.
+fn line_cells(from: ScaledPoint, to: ScaledPoint) -> Vec<(i32, i32)> {
+    let ScaledPoint(mut x, mut y) = from;
+    let ScaledPoint(x1, y1) = to;
+    let dx = (x1 - x).abs();
+    let dy = -(y1 - y).abs();
+    let step_x = if x < x1 { 1 } else { -1 };
+    let step_y = if y < y1 { 1 } else { -1 };
+    let mut err = dx + dy;
+
+    let mut cells = vec![(x, y)];
+    while (x, y) != (x1, y1) {
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += step_x;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += step_y;
+        }
+        cells.push((x, y));
+    }
+
+    cells
+}
+
+/// A row of tick labels `width` characters wide, each centred on the character
+/// holding its tick. Labels which would run into the previous one, or which are
+/// wider than the row, are left out.
+fn label_row(ticks: &[(usize, Tick)], cell_width: usize, width: usize) -> String {
+    let mut row = String::new();
+    let mut len = 0;
+    for (cell, tick) in ticks {
+        let label_len = tick.label.chars().count();
+        let start = (cell / cell_width)
+            .saturating_sub(label_len / 2)
+            .min(width.saturating_sub(label_len));
+        if (len > 0 && start <= len) || start + label_len > width {
+            continue;
+        }
+
+        row.push_str(&" ".repeat(start - len));
+        row.push_str(&tick.label);
+        len = start + label_len;
+    }
+
+    row
+}
+
+/// Shades for the cells of a heatmap, from the fewest points to the most.
+const SHADES: [&str; 4] = ["░", "▒", "▓", "█"];
+
+/// The shade for a cell of a heatmap a fraction of the way up the scale.
+fn shade(heat: f64) -> &'static str {
+    let level = (heat * SHADES.len() as f64).ceil() as usize;
+    SHADES[level.clamp(1, SHADES.len()) - 1]
+}
+
+const COL_MARKS: &[u8; 33] = b"#@*^!~%ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+fn col_mark(col: usize) -> char {
+    if col < COL_MARKS.len() {
+        COL_MARKS[col] as char
+    } else {
+        '*'
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn labels_fit_row() {
+        let tick = |label: &str| Tick {
+            value: 0.0,
+            label: label.to_string(),
+        };
+        let ticks = [(0, tick("0")), (2, tick("column 1")), (4, tick("9"))];
+        assert_eq!(label_row(&ticks, 1, 5), "0   9");
+        assert_eq!(label_row(&ticks, 1, 0), "");
+    }
+
+    #[test]
+    fn heatmap_shades() {
+        assert_eq!(shade(0.01), "░");
+        assert_eq!(shade(0.25), "░");
+        assert_eq!(shade(0.5), "▒");
+        assert_eq!(shade(0.6), "▓");
+        assert_eq!(shade(1.0), "█");
+    }
+
+    #[test]
+    fn line_cells_cover_every_step() {
+        let cells = line_cells(ScaledPoint(0, 0), ScaledPoint(4, 2));
+        assert_eq!(cells, [(0, 0), (1, 1), (2, 1), (3, 2), (4, 2)]);
+
+        let cells = line_cells(ScaledPoint(2, 3), ScaledPoint(2, 0));
+        assert_eq!(cells, [(2, 3), (2, 2), (2, 1), (2, 0)]);
+
+        assert_eq!(line_cells(ScaledPoint(1, 1), ScaledPoint(1, 1)), [(1, 1)]);
+    }
+
+    #[test]
+    fn line_path_joins_segments() {
+        let run = [
+            ScaledPoint(0, 0),
+            ScaledPoint(2, 0),
+            ScaledPoint(2, 0),
+            ScaledPoint(3, 2),
+        ];
+        assert_eq!(line_path(&run), [(0, 0), (1, 0), (2, 0), (3, 1), (3, 2)]);
+        assert_eq!(line_path(&run[..1]), [(0, 0)]);
+    }
+}
@@ -0,0 +1,191 @@
+use crate::config::Config;
+use crate::stats::quantile;
+use crate::types::{DataSet, EMPTY_VALUE};
+
+/// The most bins the automatic rules may choose, so that a long tail doesn't
+/// leave every bar too thin to see.
+const MAX_AUTO_BINS: usize = 100;
+
+/// Count the values of each column into bins shared by all the columns. The
+/// result has a row per bin, holding its centre and the count for each column,
+/// with empty bins left empty. With a log X scale, the bins are evenly spaced
+/// in log space.
+pub fn histogram(dataset: &DataSet, config: &Config) -> anyhow::Result<DataSet> {
+    let samples: Vec<Vec<f64>> = dataset
+        .points
+        .iter()
+        .map(|column| {
+            column
+                .iter()
+                .take(dataset.rows)
+                .filter(|point| !point.is_empty())
+                .map(|point| point.1)
+                .collect()
+        })
+        .collect();
+
+    if config.log_x && samples.iter().flatten().any(|&value| value <= 0.0) {
+        anyhow::bail!("Log scale requires positive values");
+    }
+    let scale = |value: f64| if config.log_x { value.ln() } else { value };
+
+    let mut scaled: Vec<f64> = samples
+        .iter()
+        .flatten()
+        .map(|&value| scale(value))
+        .collect();
+    if scaled.is_empty() {
+        anyhow::bail!("No data to plot");
+    }
+    scaled.sort_by(f64::total_cmp);
+
+    let mut min = scaled[0];
+    let max = scaled[scaled.len() - 1];
+    let (bins, width) = if max > min {
+        let bins = config.bins.unwrap_or_else(|| auto_bins(&scaled));
+        (bins, (max - min) / bins as f64)
+    } else {
+        // A single bin, centred on the only value.
+        min -= 0.5;
+        (1, 1.0)
+    };
+
+    let mut counts = vec![vec![0; bins]; samples.len()];
+    for (col, values) in samples.iter().enumerate() {
+        for &value in values {
+            // The largest value closes the last bin.
+            let bin = ((scale(value) - min) / width) as usize;
+            counts[col][bin.min(bins - 1)] += 1;
+        }
+    }
+
+    let mut binned = DataSet {
+        header: dataset.header.clone(),
+        fields: dataset.fields.clone(),
+        ..Default::default()
+    };
+    for bin in 0..bins {
+        let centre = min + width * (bin as f64 + 0.5);
+        let x = if config.log_x { centre.exp() } else { centre };
+        let row: Vec<f64> = counts
+            .iter()
+            .map(|counts| match counts[bin] {
+                0 => EMPTY_VALUE,
+                count => count as f64,
+            })
+            .collect();
+        binned.push_row(x, &row);
+    }
+
+    Ok(binned)
+}
+
+/// A number of bins for sorted samples, by the Freedman–Diaconis rule, or by
+/// Sturges' rule where the samples are too bunched up for that.
+fn auto_bins(sorted: &[f64]) -> usize {
+    let n = sorted.len() as f64;
+    let sturges = n.log2().ceil() as usize + 1;
+
+    let iqr = quantile(sorted, 0.75) - quantile(sorted, 0.25);
+    let range = sorted[sorted.len() - 1] - sorted[0];
+    let bins = if iqr > 0.0 {
+        let width = 2.0 * iqr / n.cbrt();
+        (range / width).ceil() as usize
+    } else {
+        sturges
+    };
+
+    bins.clamp(1, MAX_AUTO_BINS)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::PlotType;
+    use crate::draw::Plot;
+    use crate::types::Point;
+
+    fn samples(values: &[f64]) -> DataSet {
+        let points = values.iter().enumerate();
+        DataSet::from_columns(vec![points.map(|(i, &v)| Point(i as f64, v)).collect()])
+    }
+
+    fn counts(dataset: &DataSet) -> Vec<(f64, f64)> {
+        dataset.points[0].iter().map(|p| (p.0, p.1)).collect()
+    }
+
+    #[test]
+    fn fixed_bins() {
+        let config = Config {
+            bins: Some(2),
+            ..Default::default()
+        };
+        let binned = histogram(&samples(&[0.0, 1.0, 1.5, 3.0, 4.0]), &config).unwrap();
+        assert_eq!(counts(&binned), [(1.0, 3.0), (3.0, 2.0)]);
+
+        let binned = histogram(&samples(&[0.0, 0.5, 4.0]), &config).unwrap();
+        assert_eq!(counts(&binned), [(1.0, 2.0), (3.0, 1.0)]);
+        let config = Config {
+            bins: Some(3),
+            ..config
+        };
+        let binned = histogram(&samples(&[0.0, 0.5, 3.0]), &config).unwrap();
+        assert!(binned.points[0][1].is_empty());
+    }
+
+    #[test]
+    fn log_bins() {
+        let config = Config {
+            bins: Some(3),
+            log_x: true,
+            ..Default::default()
+        };
+        let binned = histogram(&samples(&[1.0, 5.0, 20.0, 1000.0]), &config).unwrap();
+        let centres: Vec<_> = counts(&binned).iter().map(|(x, _)| x.round()).collect();
+        assert_eq!(centres, [3.0, 32.0, 316.0]);
+        assert_eq!(binned.points[0][0].1, 2.0);
+
+        assert!(histogram(&samples(&[0.0, 1.0]), &config).is_err());
+    }
+
+    #[test]
+    fn single_value() {
+        let binned = histogram(&samples(&[2.0, 2.0]), &Config::default()).unwrap();
+        assert_eq!(counts(&binned), [(2.0, 2.0)]);
+
+        // The lone bar is centred in the plot.
+        let config = Config {
+            mode: PlotType::Histogram,
+            ..Default::default()
+        };
+        let values = samples(&[5.0]);
+        let plot = Plot::new(&values, &config).unwrap();
+        assert_eq!((plot.x_min(), plot.x_max()), (4.5, 5.5));
+        assert_eq!((plot.y_min(), plot.y_max()), (0.0, 1.0));
+    }
+
+    #[test]
+    fn values_not_times() {
+        let config = Config {
+            mode: PlotType::Histogram,
+            x_time: Some(crate::config::TimeFormat::Epoch),
+            ..Default::default()
+        };
+        let values = samples(&[1.0, 2.0, 3.0]);
+        let plot = Plot::new(&values, &config).unwrap();
+        assert!(!plot.time_x());
+        assert_eq!(plot.format_x(2.0), "2");
+    }
+
+    #[test]
+    fn auto_rules() {
+        let uniform: Vec<f64> = (0..=100).map(f64::from).collect();
+        // Freedman–Diaconis: bins 2 * 50 / 101^(1/3) ≈ 21.5 wide over a range of 100.
+        assert_eq!(auto_bins(&uniform), 5);
+
+        // Mostly the same value, so the IQR is zero and Sturges takes over.
+        let mut bunched = vec![1.0; 30];
+        bunched.push(100.0);
+        assert_eq!(auto_bins(&bunched), 6);
+    }
+}
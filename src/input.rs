@@ -1,4 +1,5 @@
-use crate::config::Config;
+use crate::config::{ColumnSelector, Config, InputFormat};
+use crate::time;
 use crate::types::{DataSet, Point, EMPTY_VALUE};
 use anyhow::Context;
 use std::io::prelude::*;
@@ -15,6 +16,20 @@ pub fn read_input(
 ) -> anyhow::Result<ReadInputStatus> {
     let mut row_count = 0;
 
+    if !config.header
+        && config
+            .columns
+            .iter()
+            .any(|c| matches!(c, ColumnSelector::Name(_)))
+    {
+        anyhow::bail!("Selecting columns by name requires --header");
+    }
+
+    if config.x_time.is_some() && config.format == InputFormat::Auto && config.x_field() != Some(0)
+    {
+        anyhow::bail!("Timestamps must be in the first field, unless --format is csv or tsv");
+    }
+
     for line in reader.lines() {
         let line = line.context("Read line from stdin")?;
 
@@ -30,12 +45,55 @@ pub fn read_input(
                     return Ok(ReadInputStatus::EndOfStream);
                 }
             }
+            ProcessLineResult::Header => check_selected_columns(config, dataset)?,
             ProcessLineResult::Comment => continue,
+            ProcessLineResult::MissingTimestamp => {
+                anyhow::bail!("Line has no timestamp in its X field: {}", line.trim())
+            }
         }
     }
 
     Ok(ReadInputStatus::EndOfStream)
 }
+
+/// Make sure every column selected by name is in the header.
+fn check_selected_columns(config: &Config, dataset: &DataSet) -> anyhow::Result<()> {
+    let header = dataset.header.as_deref().unwrap_or_default();
+    for selector in &config.columns {
+        if let ColumnSelector::Name(name) = selector {
+            if !header.contains(name) {
+                anyhow::bail!(
+                    "Column {:?} is not in the header: {}",
+                    name,
+                    header.join(", ")
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The input fields picked out by `--columns`, counting from 0, or `None` to use
+/// every field. Names not found in the header are left out.
+fn selected_fields(config: &Config, dataset: &DataSet) -> Option<Vec<usize>> {
+    if config.columns.is_empty() {
+        return None;
+    }
+
+    let header = dataset.header.as_deref().unwrap_or_default();
+    let fields = config
+        .columns
+        .iter()
+        .filter_map(|selector| match selector {
+            ColumnSelector::Index(index) => Some(index - 1),
+            ColumnSelector::Name(name) => header.iter().position(|h| h == name),
+        })
+        .collect();
+
+    Some(fields)
+}
+
 fn is_comment_marker(c: char) -> bool {
     c == '#' || c == '/'
 }
@@ -44,11 +102,55 @@ fn number_head(c: char) -> bool {
     c.is_ascii_digit() || c == '-' || c == '+' || c == '.' || c == 'e' || c == 'E'
 }
 
+/// Split a line into fields as described by RFC 4180. Fields may be quoted, in
+/// which case they can contain the delimiter, and `""` stands for a literal quote.
+/// Quoted fields spanning several lines are not supported.
+pub fn split_fields(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            if c == '"' {
+                if chars.peek() == Some(&'"') {
+                    field.push('"');
+                    chars.next();
+                } else {
+                    in_quotes = false;
+                }
+            } else {
+                field.push(c);
+            }
+        } else if c == '"' {
+            in_quotes = true;
+        } else if c == delimiter {
+            fields.push(std::mem::take(&mut field));
+        } else {
+            field.push(c);
+        }
+    }
+    fields.push(field);
+
+    fields
+}
+
+/// Split a header line into column names, at the same characters as the fields
+/// of data rows, so that each name lines up with its field.
+fn split_header(line: &str) -> Vec<String> {
+    line.split(|c: char| !number_head(c) && !c.is_alphanumeric() && c != '_')
+        .map(|name| name.trim().to_string())
+        .collect()
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum ProcessLineResult {
     Ok,
     Empty,
     Comment,
+    Header,
+    MissingTimestamp,
 }
 
 pub fn process_line(
@@ -63,39 +165,83 @@ pub fn process_line(
         return ProcessLineResult::Empty;
     }
 
+    if let Some(delimiter) = config.format.delimiter() {
+        return process_delimited_line(config, dataset, line, delimiter, row_count);
+    }
+
+    // Timestamps are read before looking for comments, as dates may contain '/'.
+    // What's left of the line starts with the delimiter after the timestamp, so
+    // the first field comes out empty and the rest keep their positions.
+    let mut line = line;
+    let mut time_x = None;
+    if let Some(format) = &config.x_time {
+        let pending_header = config.header && dataset.header.is_none();
+        let trimmed = line.trim_start();
+        if !pending_header && !trimmed.starts_with(is_comment_marker) {
+            match time::parse_time_prefix(trimmed, format) {
+                Some((t, rest)) => {
+                    time_x = Some(t);
+                    line = rest;
+                }
+                None => return ProcessLineResult::MissingTimestamp,
+            }
+        }
+    }
+
     let mut col = 0;
     let content = match line.split_once(is_comment_marker) {
-        Some((data, _comment)) => data,
+        Some((data, _comment)) => data.trim_end(),
         None => line,
     };
 
-    if content.is_empty() {
+    if content.is_empty() && time_x.is_none() {
         return ProcessLineResult::Comment;
     }
 
+    if config.header && dataset.header.is_none() {
+        dataset.header = Some(split_header(content));
+        return ProcessLineResult::Header;
+    }
+
     let mut cur_x = row_count as f64;
-    let mut has_x = false;
+    let x_field = config.x_field();
 
     let nums = content.split(|c| !number_head(c)).collect::<Vec<_>>();
-    if nums.len() == 2 && nums.iter().all(|x| x.is_empty()) {
+    if let Some(t) = time_x {
+        cur_x = t;
+    } else if let Some(x_field) = x_field {
+        cur_x = nums
+            .get(x_field)
+            .and_then(|x| parse_number(x))
+            .unwrap_or(EMPTY_VALUE);
+    }
+
+    if let Some(selection) = selected_fields(config, dataset) {
+        for (c, &field) in selection.iter().enumerate() {
+            let v = nums
+                .get(field)
+                .and_then(|v| parse_number(v))
+                .unwrap_or(EMPTY_VALUE);
+            add_field(config, dataset, row_count, c, field, Point(cur_x, v));
+        }
+        col = selection.len();
+    } else if nums.len() == 2 && nums.iter().all(|x| x.is_empty()) {
         // Slightly annoying special case, where if the string is a single invalid character,
         // split will return two empty strings, and we want to treat this as a single
         // empty value.
         let point = Point(cur_x, EMPTY_VALUE);
-        dataset.add_pair(config, row_count, col, point);
+        add_field(config, dataset, row_count, col, 0, point);
         col += 1;
     } else {
-        for num in nums {
-            let v = num.parse::<f64>().unwrap_or(EMPTY_VALUE);
-
-            if config.x_column && !has_x {
-                cur_x = v;
-                has_x = true;
-            } else {
-                let point = Point(cur_x, v);
-                dataset.add_pair(config, row_count, col, point);
-                col += 1;
+        for (field, num) in nums.into_iter().enumerate() {
+            if Some(field) == x_field {
+                continue;
             }
+
+            let v = parse_number(num).unwrap_or(EMPTY_VALUE);
+            let point = Point(cur_x, v);
+            add_field(config, dataset, row_count, col, field, point);
+            col += 1;
         }
     }
 
@@ -108,9 +254,120 @@ pub fn process_line(
     ProcessLineResult::Ok
 }
 
+/// Process a line of delimited fields. Only whole lines can be commented out, and
+/// fields with text in them never become columns, so that labels such as hostnames
+/// don't turn into numbers.
+fn process_delimited_line(
+    config: &Config,
+    dataset: &mut DataSet,
+    line: &str,
+    delimiter: char,
+    row_count: usize,
+) -> ProcessLineResult {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with('#') || trimmed.starts_with("//") {
+        return ProcessLineResult::Comment;
+    }
+
+    let fields = split_fields(line, delimiter);
+
+    if config.header && dataset.header.is_none() {
+        let names = fields.iter().map(|name| name.trim().to_string()).collect();
+        dataset.header = Some(names);
+        return ProcessLineResult::Header;
+    }
+
+    let mut cur_x = row_count as f64;
+    let x_field = config.x_field();
+    if let Some(x_field) = x_field {
+        let x = fields.get(x_field).map_or("", String::as_str);
+        let x = match &config.x_time {
+            Some(format) => match time::parse_time(x, format) {
+                Some(t) => Some(t),
+                None => return ProcessLineResult::MissingTimestamp,
+            },
+            None => parse_field(x),
+        };
+        cur_x = x.unwrap_or(EMPTY_VALUE);
+    }
+
+    if let Some(selection) = selected_fields(config, dataset) {
+        // Selected fields always become columns, even if they hold text.
+        for (col, &field) in selection.iter().enumerate() {
+            let v = fields.get(field).and_then(|v| parse_field(v));
+            let point = Point(cur_x, v.unwrap_or(EMPTY_VALUE));
+            add_field(config, dataset, row_count, col, field, point);
+        }
+
+        return ProcessLineResult::Ok;
+    }
+
+    let mut filled = vec![false; dataset.columns];
+
+    for (field, value) in fields.iter().enumerate() {
+        if Some(field) == x_field {
+            continue;
+        }
+
+        let v = parse_field(value);
+        let col = match dataset.fields.iter().position(|&f| f == field) {
+            Some(col) => col,
+            // Text, or nothing at all, until this field has held a number.
+            None if v.filter(|v| !v.is_nan()).is_none() => continue,
+            None => dataset.columns,
+        };
+
+        let point = Point(cur_x, v.unwrap_or(EMPTY_VALUE));
+        add_field(config, dataset, row_count, col, field, point);
+        filled.resize(dataset.columns, false);
+        filled[col] = true;
+    }
+
+    // Fill remaining columns with EMPTY_VALUE.
+    for (col, filled) in filled.into_iter().enumerate() {
+        if !filled {
+            dataset.add_pair(config, row_count, col, Point(cur_x, EMPTY_VALUE));
+        }
+    }
+
+    ProcessLineResult::Ok
+}
+
+/// Parse a delimited field. Empty fields are empty values, and text is `None`.
+fn parse_field(value: &str) -> Option<f64> {
+    let value = value.trim();
+    if value.is_empty() {
+        Some(EMPTY_VALUE)
+    } else {
+        parse_number(value)
+    }
+}
+
+/// Parse a number, leaving out infinities and NaN, which Rust parses from words
+/// such as "inf" that are better taken as text.
+fn parse_number(value: &str) -> Option<f64> {
+    value.parse::<f64>().ok().filter(|v| v.is_finite())
+}
+
+/// Add a point to a column, remembering which input field the column came from.
+fn add_field(
+    config: &Config,
+    dataset: &mut DataSet,
+    row: usize,
+    col: usize,
+    field: usize,
+    point: Point,
+) {
+    dataset.add_pair(config, row, col, point);
+    if col >= dataset.fields.len() {
+        dataset.fields.push(field);
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::config::TimeFormat;
 
     #[test]
     fn input_empty() {
@@ -249,7 +506,7 @@ mod test {
         let config = Config::default();
         let mut dataset = DataSet::default();
 
-        let lines = vec!["1278", "377", "316", "232", "_", "93", "63", "11"];
+        let lines = ["1278", "377", "316", "232", "_", "93", "63", "11"];
         for (i, line) in lines.iter().enumerate() {
             assert_eq!(
                 ProcessLineResult::Ok,
@@ -410,4 +667,355 @@ mod test {
 
         assert_eq!(dataset.points[0][0], exp0_0);
     }
+
+    #[test]
+    fn header_names_columns() {
+        let config = Config {
+            header: true,
+            x_column: true,
+            ..Default::default()
+        };
+        let mut dataset = DataSet::default();
+
+        assert_eq!(
+            ProcessLineResult::Comment,
+            process_line(&config, &mut dataset, "# exported from top", 0)
+        );
+        assert_eq!(
+            ProcessLineResult::Header,
+            process_line(&config, &mut dataset, "time,cpu,mem", 0)
+        );
+        assert_eq!(
+            ProcessLineResult::Ok,
+            process_line(&config, &mut dataset, "1,2,3", 0)
+        );
+
+        assert_eq!(dataset.rows, 1);
+        assert_eq!(dataset.columns, 2);
+        assert_eq!(dataset.points[0][0], Point(1.0, 2.0));
+        assert_eq!(dataset.column_name(0), Some("cpu"));
+        assert_eq!(dataset.column_name(1), Some("mem"));
+        assert_eq!(dataset.column_name(2), None);
+    }
+
+    #[test]
+    fn header_whitespace_separated() {
+        let config = Config {
+            header: true,
+            ..Default::default()
+        };
+        let mut dataset = DataSet::default();
+
+        assert_eq!(
+            ProcessLineResult::Header,
+            process_line(&config, &mut dataset, "cpu mem", 0)
+        );
+        assert_eq!(
+            ProcessLineResult::Ok,
+            process_line(&config, &mut dataset, "2 3", 0)
+        );
+
+        assert_eq!(dataset.column_name(0), Some("cpu"));
+        assert_eq!(dataset.column_name(1), Some("mem"));
+    }
+
+    #[test]
+    fn header_repeated_separators() {
+        let config = Config {
+            header: true,
+            ..Default::default()
+        };
+        let mut dataset = DataSet::default();
+
+        assert_eq!(
+            ProcessLineResult::Header,
+            process_line(&config, &mut dataset, "a  b  c", 0)
+        );
+        assert_eq!(
+            ProcessLineResult::Ok,
+            process_line(&config, &mut dataset, "1  2  3", 0)
+        );
+
+        // Each repeated separator starts an empty field, in the header as in the data.
+        assert_eq!(dataset.columns, 5);
+        assert_eq!(dataset.points[2][0].1, 2.0);
+        assert_eq!(dataset.column_name(0), Some("a"));
+        assert_eq!(dataset.column_name(2), Some("b"));
+        assert_eq!(dataset.column_name(4), Some("c"));
+    }
+
+    #[test]
+    fn split_quoted_fields() {
+        assert_eq!(split_fields("1,2,3", ','), ["1", "2", "3"]);
+        assert_eq!(split_fields("a,,b,", ','), ["a", "", "b", ""]);
+        assert_eq!(
+            split_fields(r#""web01, rack 2",5,"say ""hi""""#, ','),
+            ["web01, rack 2", "5", r#"say "hi""#]
+        );
+        assert_eq!(split_fields("1\t\"a\tb\"\t3", '\t'), ["1", "a\tb", "3"]);
+    }
+
+    #[test]
+    fn csv_skips_text_columns() {
+        let config = Config {
+            format: InputFormat::Csv,
+            ..Default::default()
+        };
+        let mut dataset = DataSet::default();
+
+        let lines = [r#"web01,"1,000",2"#, "web02,,3", "web03,N/A,4"];
+        for (i, line) in lines.iter().enumerate() {
+            assert_eq!(
+                ProcessLineResult::Ok,
+                process_line(&config, &mut dataset, line, i)
+            );
+        }
+
+        // "1,000" is not a number either, so the second field only becomes a
+        // column once it holds one.
+        assert_eq!(dataset.rows, 3);
+        assert_eq!(dataset.columns, 1);
+        assert_eq!(dataset.fields, [2]);
+        assert_eq!(dataset.points[0][0], Point(0.0, 2.0));
+        assert_eq!(dataset.points[0][2], Point(2.0, 4.0));
+    }
+
+    #[test]
+    fn csv_non_finite_words_are_text() {
+        let config = Config {
+            format: InputFormat::Csv,
+            ..Default::default()
+        };
+        let mut dataset = DataSet::default();
+
+        let lines = ["inf,NaN,1", "infinity,nan,2"];
+        for (i, line) in lines.iter().enumerate() {
+            process_line(&config, &mut dataset, line, i);
+        }
+
+        assert_eq!(dataset.columns, 1);
+        assert_eq!(dataset.fields, [2]);
+        assert_eq!(dataset.points[0][1], Point(1.0, 2.0));
+    }
+
+    #[test]
+    fn csv_header_and_x_column() {
+        let config = Config {
+            format: InputFormat::Csv,
+            header: true,
+            x_column: true,
+            ..Default::default()
+        };
+        let mut dataset = DataSet::default();
+
+        let lines = [
+            "# hosts",
+            r#"time,host,"load, 1m""#,
+            "10,web01,0.5",
+            "20,web02,",
+            "30,web03,1.5",
+        ];
+        let mut row = 0;
+        for line in lines {
+            if process_line(&config, &mut dataset, line, row) == ProcessLineResult::Ok {
+                row += 1;
+            }
+        }
+
+        assert_eq!(dataset.rows, 3);
+        assert_eq!(dataset.columns, 1);
+        assert_eq!(dataset.column_name(0), Some("load, 1m"));
+        assert_eq!(dataset.points[0][0], Point(10.0, 0.5));
+        assert_eq!(dataset.points[0][1], Point(20.0, EMPTY_VALUE));
+        assert_eq!(dataset.points[0][2], Point(30.0, 1.5));
+    }
+
+    #[test]
+    fn tsv_fields() {
+        let config = Config {
+            format: InputFormat::Tsv,
+            ..Default::default()
+        };
+        let mut dataset = DataSet::default();
+
+        assert_eq!(
+            ProcessLineResult::Ok,
+            process_line(&config, &mut dataset, "1\t-2e3\t3,5", 0)
+        );
+
+        assert_eq!(dataset.columns, 2);
+        assert_eq!(dataset.points[0][0], Point(0.0, 1.0));
+        assert_eq!(dataset.points[1][0], Point(0.0, -2e3));
+    }
+
+    #[test]
+    fn select_columns_by_index() {
+        let config = Config {
+            columns: vec![ColumnSelector::Index(4), ColumnSelector::Index(2)],
+            x_column_index: Some(3),
+            ..Default::default()
+        };
+        let mut dataset = DataSet::default();
+
+        assert_eq!(
+            ProcessLineResult::Ok,
+            process_line(&config, &mut dataset, "1 2 3 4 5", 0)
+        );
+        assert_eq!(
+            ProcessLineResult::Ok,
+            process_line(&config, &mut dataset, "6 7 8", 1)
+        );
+
+        assert_eq!(dataset.rows, 2);
+        assert_eq!(dataset.columns, 2);
+        assert_eq!(dataset.fields, [3, 1]);
+        assert_eq!(dataset.points[0][0], Point(3.0, 4.0));
+        assert_eq!(dataset.points[1][0], Point(3.0, 2.0));
+        assert_eq!(dataset.points[0][1], Point(8.0, EMPTY_VALUE));
+        assert_eq!(dataset.points[1][1], Point(8.0, 7.0));
+    }
+
+    #[test]
+    fn x_column_index_skips_field() {
+        let config = Config {
+            x_column_index: Some(2),
+            ..Default::default()
+        };
+        let mut dataset = DataSet::default();
+
+        assert_eq!(
+            ProcessLineResult::Ok,
+            process_line(&config, &mut dataset, "1,10,3", 0)
+        );
+
+        assert_eq!(dataset.columns, 2);
+        assert_eq!(dataset.fields, [0, 2]);
+        assert_eq!(dataset.points[0][0], Point(10.0, 1.0));
+        assert_eq!(dataset.points[1][0], Point(10.0, 3.0));
+    }
+
+    #[test]
+    fn select_columns_by_name() {
+        let config = Config {
+            format: InputFormat::Csv,
+            header: true,
+            columns: vec![
+                ColumnSelector::Name("mem".into()),
+                ColumnSelector::Name("host".into()),
+            ],
+            x_column: true,
+            ..Default::default()
+        };
+        let mut dataset = DataSet::default();
+
+        let input = "time,host,cpu,mem\n10,web01,0.5,300\n";
+        let mut reader = std::io::BufReader::new(input.as_bytes());
+        read_input(&config, &mut dataset, &mut reader).unwrap();
+
+        assert_eq!(dataset.rows, 1);
+        assert_eq!(dataset.columns, 2);
+        assert_eq!(dataset.column_name(0), Some("mem"));
+        assert_eq!(dataset.column_name(1), Some("host"));
+        assert_eq!(dataset.points[0][0], Point(10.0, 300.0));
+        assert_eq!(dataset.points[1][0], Point(10.0, EMPTY_VALUE));
+    }
+
+    #[test]
+    fn select_unknown_column_name() {
+        let config = Config {
+            header: true,
+            columns: vec![ColumnSelector::Name("disk".into())],
+            ..Default::default()
+        };
+        let mut dataset = DataSet::default();
+
+        let mut reader = std::io::BufReader::new("cpu mem\n1 2\n".as_bytes());
+        assert!(read_input(&config, &mut dataset, &mut reader).is_err());
+    }
+
+    #[test]
+    fn x_time_auto() {
+        let config = Config {
+            x_time: Some(TimeFormat::Auto),
+            ..Default::default()
+        };
+        let mut dataset = DataSet::default();
+
+        let lines = [
+            "2026-10-17T12:00:00Z,42",
+            "2026/10/17 12:01:00 43 // note",
+            "not a time,44",
+        ];
+        assert_eq!(
+            ProcessLineResult::Ok,
+            process_line(&config, &mut dataset, lines[0], 0)
+        );
+        assert_eq!(
+            ProcessLineResult::Ok,
+            process_line(&config, &mut dataset, lines[1], 1)
+        );
+        assert_eq!(
+            ProcessLineResult::MissingTimestamp,
+            process_line(&config, &mut dataset, lines[2], 2)
+        );
+
+        assert_eq!(dataset.rows, 2);
+        assert_eq!(dataset.columns, 1);
+        assert_eq!(dataset.points[0][0], Point(1_792_238_400.0, 42.0));
+        assert_eq!(dataset.points[0][1], Point(1_792_238_460.0, 43.0));
+    }
+
+    #[test]
+    fn x_time_missing_is_error() {
+        let config = Config {
+            x_time: Some(TimeFormat::Epoch),
+            ..Default::default()
+        };
+        let mut dataset = DataSet::default();
+        let mut reader = "1792238400 1\n# note\nn/a 3\n".as_bytes();
+
+        let err = read_input(&config, &mut dataset, &mut reader).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Line has no timestamp in its X field: n/a 3"
+        );
+    }
+
+    #[test]
+    fn x_time_csv_missing() {
+        let config = Config {
+            format: InputFormat::Csv,
+            x_time: Some(TimeFormat::Auto),
+            x_column_index: Some(2),
+            ..Default::default()
+        };
+        let mut dataset = DataSet::default();
+
+        assert_eq!(
+            ProcessLineResult::MissingTimestamp,
+            process_line(&config, &mut dataset, "5,yesterday", 0)
+        );
+        let mut reader = "5,2026-10-17\n6,\n".as_bytes();
+        let err = read_input(&config, &mut dataset, &mut reader).unwrap_err();
+        assert_eq!(err.to_string(), "Line has no timestamp in its X field: 6,");
+    }
+
+    #[test]
+    fn x_time_csv_column() {
+        let config = Config {
+            format: InputFormat::Csv,
+            x_time: Some(TimeFormat::EpochMillis),
+            x_column_index: Some(2),
+            ..Default::default()
+        };
+        let mut dataset = DataSet::default();
+
+        assert_eq!(
+            ProcessLineResult::Ok,
+            process_line(&config, &mut dataset, "5,1792238400000", 0)
+        );
+
+        assert_eq!(dataset.columns, 1);
+        assert_eq!(dataset.points[0][0], Point(1_792_238_400.0, 5.0));
+    }
 }
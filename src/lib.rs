@@ -0,0 +1,47 @@
+//! A simple command-line chart utility, usable as a library.
+//!
+//! Build a [`DataSet`], wrap it in a [`Plot`], and hand it to one of the
+//! [`Renderer`]s to write it out:
+//!
+//! ```
+//! use plort::{AsciiPlot, DataSet, Plot, Point, Renderer};
+//!
+//! let dataset = DataSet::from_columns(vec![vec![
+//!     Point(0.0, 1.0),
+//!     Point(1.0, 4.0),
+//!     Point(2.0, 9.0),
+//! ]]);
+//!
+//! let plot = Plot::builder(&dataset)
+//!     .dimensions(40, 10)
+//!     .regression(false)
+//!     .build()?;
+//!
+//! let mut out = Vec::new();
+//! AsciiPlot.render(&plot, &mut out)?;
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+pub mod color;
+pub mod config;
+mod config_file;
+pub mod draw;
+mod histogram;
+pub(crate) mod input;
+mod regression;
+pub(crate) mod scale;
+pub(crate) mod stats;
+pub mod theme;
+pub(crate) mod ticks;
+pub(crate) mod time;
+pub mod types;
+mod utf;
+
+pub use config::Config;
+pub use draw::html::HtmlPlot;
+pub use draw::svg::SvgPlot;
+pub use draw::text::{ascii::AsciiPlot, braille::BraillePlot};
+pub use draw::{Plot, PlotBuilder, Renderer};
+pub use input::{read_input, ReadInputStatus};
+pub use theme::Theme;
+pub use types::{DataSet, Point};
@@ -1,37 +1,69 @@
-use std::io::BufRead;
+use std::ffi::OsString;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
 
 use anyhow::Context;
-use clap::Parser;
-use config::Config;
-use draw::draw;
-use input::{read_input, ReadInputStatus};
-use types::DataSet;
-
-mod config;
-mod draw;
-mod input;
-mod regression;
-mod scale;
-mod types;
-mod utf;
+use plort::color::ColorDepth;
+use plort::config::{Dimensions, OutputType};
+use plort::draw::draw;
+use plort::{read_input, Config, DataSet, ReadInputStatus};
+use terminal_size::{Height, Width};
+
+/// Rows of the terminal taken by the header above the plot and the prompt
+/// below it.
+const RESERVED_ROWS: usize = 2;
 
 fn main() -> anyhow::Result<()> {
     pretty_env_logger::init();
-    let config = Config::parse();
+    let mut config = Config::load()?;
+
+    // Work out the file's output type before reading any input, so that a bad
+    // extension is reported straight away.
+    let output_file = match &config.output_file {
+        Some(path) => {
+            let output_type =
+                OutputType::for_path(path, config.output_type).map_err(anyhow::Error::msg)?;
+            // The size is in characters for text and pixels otherwise, so only
+            // carries over to a file drawn in the same units as --output.
+            let dimensions = if output_type.is_text() == config.output_type.is_text() {
+                config.dimensions
+            } else {
+                None
+            };
+            let file_config = Config {
+                output_type,
+                dimensions,
+                // Escape codes only get in the way in a file.
+                color_depth: ColorDepth::None,
+                ..config.clone()
+            };
+            Some((path.clone(), file_config))
+        }
+        None => None,
+    };
+
+    if config.dimensions.is_none() && config.output_type.is_text() {
+        config.dimensions = terminal_dimensions();
+    }
 
     if let Some(filepath) = &config.filename {
         let file = std::fs::File::open(filepath)?;
         let mut reader = std::io::BufReader::new(file);
-        main_loop(&config, &mut reader)?;
+        main_loop(&config, &output_file, &mut reader)?;
     } else {
         let mut reader = std::io::stdin().lock();
-        main_loop(&config, &mut reader)?;
+        main_loop(&config, &output_file, &mut reader)?;
     }
 
     Ok(())
 }
 
-fn main_loop(config: &Config, reader: &mut impl BufRead) -> anyhow::Result<()> {
+fn main_loop(
+    config: &Config,
+    output_file: &Option<(PathBuf, Config)>,
+    reader: &mut impl BufRead,
+) -> anyhow::Result<()> {
+    let mut out = std::io::stdout().lock();
     let mut dataset = DataSet::default();
     let mut end_of_stream = false;
     while !end_of_stream {
@@ -44,11 +76,58 @@ fn main_loop(config: &Config, reader: &mut impl BufRead) -> anyhow::Result<()> {
             return Ok(());
         }
 
-        draw(config, &dataset).context("Drawing diagram")?;
-        if !end_of_stream {
-            println!();
+        // In stream mode the file is replaced with each new plot.
+        if let Some((path, file_config)) = output_file {
+            let mut buf = Vec::new();
+            draw(file_config, &dataset, &mut buf).context("Drawing diagram")?;
+            write_atomic(path, &buf).with_context(|| format!("Writing {}", path.display()))?;
+        }
+
+        if output_file.is_none() || config.tee {
+            draw(config, &dataset, &mut out).context("Drawing diagram")?;
+            if !end_of_stream {
+                writeln!(out)?;
+            }
+            out.flush()?;
         }
     }
 
     Ok(())
 }
+
+/// Write a file by way of a temporary file beside it, so that readers never see
+/// it half written.
+fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut name = OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(".tmp");
+    let tmp = path.with_file_name(name);
+
+    let result = std::fs::write(&tmp, contents).and_then(|()| std::fs::rename(&tmp, path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    result
+}
+
+/// The size of the terminal on stdout, less the rows around the plot, or None
+/// if stdout isn't a terminal.
+fn terminal_dimensions() -> Option<Dimensions> {
+    if !std::io::stdout().is_terminal() {
+        return None;
+    }
+
+    let (width, height) = match terminal_size::terminal_size() {
+        Some((Width(width), Height(height))) => (width as usize, height as usize),
+        None => (env_size("COLUMNS")?, env_size("LINES")?),
+    };
+
+    Some(Dimensions {
+        width,
+        height: height.saturating_sub(RESERVED_ROWS).max(1),
+    })
+}
+
+fn env_size(name: &str) -> Option<usize> {
+    std::env::var(name).ok()?.trim().parse().ok()
+}
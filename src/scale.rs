@@ -1,8 +1,13 @@
-use crate::{draw::Plot, types::Point};
+use crate::types::Point;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ScaledPoint(pub i32, pub i32);
 
+/// A point scaled to a plot area like a [`ScaledPoint`], but not rounded to a
+/// cell, for drawing with sub-pixel precision.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExactPoint(pub f64, pub f64);
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TransformType {
     None,
@@ -32,53 +37,41 @@ pub struct Bounds {
     pub height: usize,
 }
 
-impl ScaledPoint {
-    pub fn new_from_bounds(
-        point: Point,
-        transform: TransformType,
-        Bounds {
-            x_min,
-            x_max,
-            y_min,
-            y_max,
-            width,
-            height,
-        }: Bounds,
-    ) -> Self {
-        let x_range = x_max - x_min;
-        let y_range = y_max - y_min;
-        let pad = 2;
-        let Point(x, y) = point.scale_transform(transform);
-        debug_assert!(x >= x_min - f64::EPSILON);
-        debug_assert!(x <= x_max + f64::EPSILON);
-
-        let cell_w: f64 = x_range / (width as f64);
-        let cell_h: f64 = y_range / (height as f64);
+/// Cells left unused at the far end of each axis, so that the largest values
+/// don't land on the edge.
+const PAD: f64 = 2.0;
+
+impl Bounds {
+    /// How far along a (log scaled) X value is, in cells from the left, as a
+    /// fraction. A point is drawn in the cell this falls in.
+    pub fn x_level(&self, x: f64) -> f64 {
+        let x_range = self.x_max - self.x_min;
+        let cell_w = x_range / self.width as f64;
+        (self.width as f64 - PAD) * ((x - self.x_min + cell_w / 2.0) / x_range)
+    }
 
-        let ox = ((width as f64 - pad as f64) * ((x - x_min + cell_w / 2.0) / x_range)) as i32;
+    /// How far up a (log scaled) Y value is, in cells from the bottom, as a
+    /// fraction.
+    pub fn y_level(&self, y: f64) -> f64 {
+        let y_range = self.y_max - self.y_min;
+        let cell_h = y_range / self.height as f64;
+        (self.height as f64 - PAD) * ((y - self.y_min + cell_h / 2.0) / y_range)
+    }
+}
 
-        let oy = ((height as f64 - pad as f64) * ((y - y_min + cell_h / 2.0) / y_range)) as i32;
+impl ScaledPoint {
+    pub fn new_from_bounds(point: Point, transform: TransformType, bounds: Bounds) -> Self {
+        let Point(x, y) = point.scale_transform(transform);
+        debug_assert!(x >= bounds.x_min - f64::EPSILON);
+        debug_assert!(x <= bounds.x_max + f64::EPSILON);
 
-        let oy = height as i32 - oy - 1;
+        let ox = bounds.x_level(x) as i32;
+        let oy = bounds.y_level(y) as i32;
+        let oy = bounds.height as i32 - oy - 1;
 
         ScaledPoint(ox, oy)
     }
 
-    pub fn new_from_plot(point: Point, plot_info: &Plot, transform: TransformType) -> Self {
-        Self::new_from_bounds(
-            point,
-            transform,
-            Bounds {
-                x_min: plot_info.x_min(),
-                x_max: plot_info.x_max(),
-                y_min: plot_info.y_min(),
-                y_max: plot_info.y_max(),
-                width: plot_info.width(),
-                height: plot_info.height(),
-            },
-        )
-    }
-
     pub fn x(&self) -> i32 {
         self.0
     }
@@ -88,6 +81,22 @@ impl ScaledPoint {
     }
 }
 
+impl ExactPoint {
+    /// Where a point falls in the plot area, where a [`ScaledPoint`] at the same
+    /// place would be drawn from the nearest whole cell.
+    pub fn new_from_bounds(point: Point, transform: TransformType, bounds: &Bounds) -> Self {
+        let Point(x, y) = point.scale_transform(transform);
+        debug_assert!(x >= bounds.x_min - f64::EPSILON);
+        debug_assert!(x <= bounds.x_max + f64::EPSILON);
+
+        // The levels are offset by half a cell, so that truncating them rounds.
+        let ox = bounds.x_level(x) - 0.5;
+        let oy = bounds.height as f64 - 0.5 - bounds.y_level(y);
+
+        ExactPoint(ox, oy)
+    }
+}
+
 #[cfg(test)]
 mod test {
 
@@ -118,6 +127,37 @@ mod test {
         assert_eq!(out_logxy, Point(f64::ln(10.0), f64::ln(100.0)));
     }
 
+    #[test]
+    fn exact_points() {
+        let bounds = Bounds {
+            x_min: 0.0,
+            x_max: 100.0,
+            y_min: 0.0,
+            y_max: 100.0,
+            width: 102,
+            height: 52,
+        };
+
+        let transform = TransformType::None;
+        for p in [
+            Point(0.0, 0.0),
+            Point(50.0, 25.0),
+            Point(100.0, 100.0),
+            Point(33.3, 66.6),
+        ] {
+            let sp = ScaledPoint::new_from_bounds(p, transform, bounds.clone());
+            let ep = ExactPoint::new_from_bounds(p, transform, &bounds);
+            assert_eq!(ep.0.round() as i32, sp.0);
+            assert_eq!(ep.1.round() as i32, sp.1);
+        }
+
+        // Values within a cell of each other are still told apart.
+        let a = ExactPoint::new_from_bounds(Point(50.0, 50.0), transform, &bounds);
+        let b = ExactPoint::new_from_bounds(Point(50.25, 50.5), transform, &bounds);
+        assert!((b.0 - a.0 - 0.25).abs() < 1e-9);
+        assert!((a.1 - b.1 - 0.25).abs() < 1e-9);
+    }
+
     #[test]
     fn scale_basic() {
         let bounds = Bounds {
@@ -0,0 +1,76 @@
+/// The value a fraction of the way through sorted samples, interpolating
+/// between neighbours.
+pub fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let pos = q * (sorted.len() - 1) as f64;
+    let below = pos.floor() as usize;
+    let above = pos.ceil() as usize;
+    sorted[below] + (sorted[above] - sorted[below]) * (pos - below as f64)
+}
+
+/// The summary drawn by a box plot. The whiskers reach the furthest values
+/// within 1.5 times the interquartile range of the box, and values beyond them
+/// are outliers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoxStats {
+    pub min: f64,
+    pub q1: f64,
+    pub median: f64,
+    pub q3: f64,
+    pub max: f64,
+    pub outliers: Vec<f64>,
+}
+
+impl BoxStats {
+    /// Summarise some values, or None if there are none.
+    pub fn new(values: &[f64]) -> Option<Self> {
+        let mut sorted = values.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        if sorted.is_empty() {
+            return None;
+        }
+
+        let q1 = quantile(&sorted, 0.25);
+        let q3 = quantile(&sorted, 0.75);
+        let reach = 1.5 * (q3 - q1);
+        let (low, high) = (q1 - reach, q3 + reach);
+        let (inside, outliers): (Vec<f64>, Vec<f64>) =
+            sorted.iter().partition(|&&v| low <= v && v <= high);
+
+        Some(BoxStats {
+            min: inside[0],
+            q1,
+            median: quantile(&sorted, 0.5),
+            q3,
+            max: inside[inside.len() - 1],
+            outliers,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn quantiles() {
+        let sorted = [1.0, 2.0, 3.0, 4.0];
+        assert_eq!(quantile(&sorted, 0.0), 1.0);
+        assert_eq!(quantile(&sorted, 0.5), 2.5);
+        assert_eq!(quantile(&sorted, 0.25), 1.75);
+        assert_eq!(quantile(&sorted, 1.0), 4.0);
+    }
+
+    #[test]
+    fn box_stats() {
+        let stats = BoxStats::new(&[5.0, 1.0, 3.0, 2.0, 4.0, 30.0, -20.0]).unwrap();
+        assert_eq!(stats.q1, 1.5);
+        assert_eq!(stats.median, 3.0);
+        assert_eq!(stats.q3, 4.5);
+        assert_eq!((stats.min, stats.max), (1.0, 5.0));
+        assert_eq!(stats.outliers, [-20.0, 30.0]);
+
+        assert_eq!(BoxStats::new(&[]), None);
+        let single = BoxStats::new(&[2.0]).unwrap();
+        assert_eq!((single.min, single.median, single.max), (2.0, 2.0, 2.0));
+    }
+}
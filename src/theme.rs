@@ -0,0 +1,302 @@
+use std::str::FromStr;
+
+pub type Rgb = (u8, u8, u8);
+
+/// The background a theme is meant to be seen against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    Light,
+    Dark,
+}
+
+impl FromStr for Variant {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "light" => Ok(Variant::Light),
+            "dark" => Ok(Variant::Dark),
+            _ => Err(format!("Unknown theme variant: {}", s)),
+        }
+    }
+}
+
+/// The shape drawn for each point of a scatter plot, where the output can draw
+/// shapes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Marker {
+    Circle,
+    Square,
+    Cross,
+}
+
+impl FromStr for Marker {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "circle" => Ok(Marker::Circle),
+            "square" => Ok(Marker::Square),
+            "cross" => Ok(Marker::Cross),
+            _ => Err(format!("Unknown marker: {}", s)),
+        }
+    }
+}
+
+/// A sequential colour scale, for showing how many points fall in each cell of
+/// a heatmap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorMap {
+    #[default]
+    Viridis,
+    Magma,
+    /// From the theme's grid colour up to its foreground colour.
+    Greys,
+}
+
+impl ColorMap {
+    /// The colour a fraction of the way along the scale.
+    pub fn color(self, t: f64, theme: &Theme) -> Rgb {
+        let stops: &[Rgb] = match self {
+            ColorMap::Viridis => &VIRIDIS,
+            ColorMap::Magma => &MAGMA,
+            ColorMap::Greys => &[theme.grid, theme.foreground],
+        };
+
+        let pos = t.clamp(0.0, 1.0) * (stops.len() - 1) as f64;
+        let i = (pos as usize).min(stops.len() - 2);
+        let frac = pos - i as f64;
+        let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * frac).round() as u8;
+        let ((r1, g1, b1), (r2, g2, b2)) = (stops[i], stops[i + 1]);
+        (mix(r1, r2), mix(g1, g2), mix(b1, b2))
+    }
+}
+
+impl FromStr for ColorMap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "viridis" => Ok(ColorMap::Viridis),
+            "magma" => Ok(ColorMap::Magma),
+            "greys" | "grays" => Ok(ColorMap::Greys),
+            _ => Err(format!("Unknown colour map: {}", s)),
+        }
+    }
+}
+
+/// Colours and line styles, shared by every renderer. Text renderers leave the
+/// background to the terminal, and draw their own marks in place of markers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub background: Rgb,
+    /// Text and borders.
+    pub foreground: Rgb,
+    pub axis: Rgb,
+    pub grid: Rgb,
+    pub series: Vec<Rgb>,
+    pub line_width: f64,
+    pub axis_width: f64,
+    pub border_width: f64,
+    pub marker: Marker,
+    pub marker_size: f64,
+}
+
+impl Theme {
+    pub fn series_color(&self, col: usize) -> Rgb {
+        self.series[col % self.series.len()]
+    }
+
+    pub fn axis_color(&self) -> Rgb {
+        self.axis
+    }
+
+    /// A built in theme by name, in the given variant or the one which suits
+    /// the scheme best.
+    pub fn builtin(name: &str, variant: Option<Variant>) -> Option<Theme> {
+        let (default_variant, light, dark): (_, &[Rgb], &[Rgb]) = match name {
+            "bank-wong" => (
+                Variant::Dark,
+                &BANK_WONG,
+                // Black is swapped for white, to be seen on a dark background.
+                &[
+                    (0, 114, 178),
+                    (230, 159, 0),
+                    (86, 180, 233),
+                    (0, 158, 115),
+                    (240, 228, 66),
+                    (255, 255, 255),
+                    (213, 94, 0),
+                    (204, 121, 167),
+                ],
+            ),
+            // Light marks, toned down on a light background.
+            "mono-light" => (Variant::Dark, &[(96, 96, 96)], &[(255, 255, 255)]),
+            // Dark marks, toned up on a dark background.
+            "mono-dark" => (Variant::Light, &[(0, 0, 0)], &[(176, 176, 176)]),
+            _ => return None,
+        };
+
+        let variant = variant.unwrap_or(default_variant);
+        let mono = name.starts_with("mono-");
+        let theme = match variant {
+            Variant::Light => Theme {
+                background: (255, 255, 255),
+                foreground: (0, 0, 0),
+                axis: if mono { light[0] } else { (123, 123, 125) },
+                grid: (224, 224, 224),
+                series: light.to_vec(),
+                ..BASE
+            },
+            Variant::Dark => Theme {
+                background: (0, 0, 0),
+                foreground: (255, 255, 255),
+                axis: if mono { dark[0] } else { (123, 123, 125) },
+                grid: (48, 48, 48),
+                series: dark.to_vec(),
+                ..BASE
+            },
+        };
+
+        Some(theme)
+    }
+}
+
+impl FromStr for Theme {
+    type Err = String;
+
+    /// A built in theme's name, optionally followed by `:light` or `:dark`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, variant) = match s.split_once(':') {
+            Some((name, variant)) => (name, Some(variant.parse()?)),
+            None => (s, None),
+        };
+
+        Theme::builtin(name, variant).ok_or_else(|| format!("Unknown color scheme: {}", s))
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::builtin("bank-wong", None).expect("bank-wong is built in")
+    }
+}
+
+/// Styles shared by the built in themes.
+const BASE: Theme = Theme {
+    background: (0, 0, 0),
+    foreground: (255, 255, 255),
+    axis: (123, 123, 125),
+    grid: (48, 48, 48),
+    series: Vec::new(),
+    line_width: 2.0,
+    axis_width: 2.0,
+    border_width: 2.0,
+    marker: Marker::Circle,
+    marker_size: 3.0,
+};
+
+/// Bang Wong's colour-blind safe palette, from Nature Methods 8, 441 (2011).
+const BANK_WONG: [Rgb; 8] = [
+    (0, 114, 178),
+    (230, 159, 0),
+    (86, 180, 233),
+    (0, 158, 115),
+    (240, 228, 66),
+    (0, 0, 0),
+    (213, 94, 0),
+    (204, 121, 167),
+];
+
+/// Evenly spaced stops along matplotlib's viridis and magma colour maps.
+const VIRIDIS: [Rgb; 5] = [
+    (68, 1, 84),
+    (59, 82, 139),
+    (33, 145, 140),
+    (94, 201, 98),
+    (253, 231, 37),
+];
+const MAGMA: [Rgb; 5] = [
+    (0, 0, 4),
+    (81, 18, 124),
+    (183, 55, 121),
+    (252, 137, 97),
+    (252, 253, 191),
+];
+
+/// A colour as an SVG/CSS hex string.
+pub fn hex((r, g, b): Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// Parse a colour written as `#rrggbb` or `#rgb`.
+pub fn parse_hex(s: &str) -> Result<Rgb, String> {
+    let err = || format!("Invalid colour: {}", s);
+    let digits = s.strip_prefix('#').ok_or_else(err)?;
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(err());
+    }
+
+    let channel = |i: usize, len: usize| {
+        let value = u8::from_str_radix(&digits[i * len..(i + 1) * len], 16).unwrap_or(0);
+        // #abc is short for #aabbcc.
+        if len == 1 {
+            value * 17
+        } else {
+            value
+        }
+    };
+    match digits.len() {
+        3 => Ok((channel(0, 1), channel(1, 1), channel(2, 1))),
+        6 => Ok((channel(0, 2), channel(1, 2), channel(2, 2))),
+        _ => Err(err()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_variants() {
+        let dark: Theme = "bank-wong".parse().unwrap();
+        assert_eq!(dark.background, (0, 0, 0));
+        assert_eq!(dark, "bank-wong:dark".parse().unwrap());
+
+        let light: Theme = "bank-wong:light".parse().unwrap();
+        assert_eq!(light.background, (255, 255, 255));
+        assert_eq!(light.series_color(5), (0, 0, 0));
+        assert_eq!(light.series_color(8), light.series_color(0));
+
+        let mono: Theme = "mono-dark".parse().unwrap();
+        assert_eq!(mono.background, (255, 255, 255));
+        assert_eq!(mono.series, [(0, 0, 0)]);
+    }
+
+    #[test]
+    fn parse_unknown() {
+        assert!("bank-wong:dim".parse::<Theme>().is_err());
+        assert!("viridis".parse::<Theme>().is_err());
+    }
+
+    #[test]
+    fn color_maps() {
+        let theme = Theme::default();
+        assert_eq!(ColorMap::Viridis.color(0.0, &theme), (68, 1, 84));
+        assert_eq!(ColorMap::Viridis.color(1.0, &theme), (253, 231, 37));
+        assert_eq!(ColorMap::Magma.color(0.125, &theme), (41, 9, 64));
+        assert_eq!(ColorMap::Greys.color(0.0, &theme), theme.grid);
+        assert_eq!(ColorMap::Greys.color(2.0, &theme), theme.foreground);
+        assert!("jet".parse::<ColorMap>().is_err());
+    }
+
+    #[test]
+    fn hex_colors() {
+        assert_eq!(hex((0, 114, 178)), "#0072b2");
+        assert_eq!(parse_hex("#0072b2"), Ok((0, 114, 178)));
+        assert_eq!(parse_hex("#fA0"), Ok((255, 170, 0)));
+        assert!(parse_hex("0072b2").is_err());
+        assert!(parse_hex("#0072b").is_err());
+        assert!(parse_hex("#00é2b2").is_err());
+    }
+}
@@ -0,0 +1,344 @@
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+
+/// A labelled position along an axis, in data units.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tick {
+    pub value: f64,
+    pub label: String,
+}
+
+/// Ticks at multiples of 1, 2 or 5 × 10^n between min and max, spaced so that
+/// the range holds at most `max_ticks` steps.
+pub fn nice_ticks(min: f64, max: f64, max_ticks: usize) -> Vec<Tick> {
+    let range = max - min;
+    if !range.is_finite() || range <= 0.0 {
+        return Vec::new();
+    }
+
+    let step = nice_step(range / max_ticks.max(1) as f64);
+    // Enough decimal places to tell neighbouring ticks apart.
+    let decimals = (-step.log10().floor()).max(0.0) as usize;
+
+    let first = (min / step).ceil() as i64;
+    let last = (max / step).floor() as i64;
+    (first..=last)
+        .map(|i| {
+            // Multiplying rather than accumulating keeps rounding errors out of
+            // the values, and zero is never shown as "-0".
+            let value = if i == 0 { 0.0 } else { i as f64 * step };
+            Tick {
+                value,
+                label: format!("{:.*}", decimals, value),
+            }
+        })
+        .filter(|tick| (min..=max).contains(&tick.value))
+        .collect()
+}
+
+/// Ticks for an axis running from min to max, which are logarithms of the data
+/// on a log scale.
+pub fn value_ticks(min: f64, max: f64, log: bool, max_ticks: usize) -> Vec<Tick> {
+    if log {
+        // Round trips through exp and ln can land just outside the range.
+        log_ticks(min.exp(), max.exp(), max_ticks)
+            .into_iter()
+            .filter(|tick| (min..=max).contains(&tick.value.ln()))
+            .collect()
+    } else {
+        nice_ticks(min, max, max_ticks)
+    }
+}
+
+/// The smallest step of 1, 2 or 5 × 10^n which is at least `raw`.
+fn nice_step(raw: f64) -> f64 {
+    let magnitude = 10f64.powf(raw.log10().floor());
+    [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .map(|m| m * magnitude)
+        .find(|step| *step >= raw * (1.0 - 1e-9))
+        .unwrap_or(10.0 * magnitude)
+}
+
+/// Ticks for a log scale covering min to max, both positive: powers of ten where
+/// the range spans enough of them, or nice numbers otherwise.
+pub fn log_ticks(min: f64, max: f64, max_ticks: usize) -> Vec<Tick> {
+    let first = min.log10().ceil() as i32;
+    let last = max.log10().floor() as i32;
+    if last - first < 1 {
+        return nice_ticks(min, max, max_ticks)
+            .into_iter()
+            .filter(|tick| tick.value > 0.0)
+            .collect();
+    }
+
+    let every = ((last - first) as usize / max_ticks.max(1) + 1) as i32;
+    (first..=last)
+        .filter(|e| (e - first) % every == 0)
+        .map(|e| {
+            // Parsed rather than computed, so that 0.01 isn't 0.010000000000000002.
+            let value: f64 = format!("1e{}", e).parse().unwrap_or(f64::NAN);
+            Tick {
+                value,
+                label: value.to_string(),
+            }
+        })
+        .collect()
+}
+
+const MINUTE: i64 = 60;
+const HOUR: i64 = 60 * MINUTE;
+const DAY: i64 = 24 * HOUR;
+
+/// Steps between time ticks, from finest to coarsest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TimeStep {
+    Seconds(i64),
+    Days(i64),
+    Months(u32),
+}
+
+const TIME_STEPS: &[TimeStep] = &[
+    TimeStep::Seconds(1),
+    TimeStep::Seconds(5),
+    TimeStep::Seconds(15),
+    TimeStep::Seconds(30),
+    TimeStep::Seconds(MINUTE),
+    TimeStep::Seconds(5 * MINUTE),
+    TimeStep::Seconds(15 * MINUTE),
+    TimeStep::Seconds(30 * MINUTE),
+    TimeStep::Seconds(HOUR),
+    TimeStep::Seconds(3 * HOUR),
+    TimeStep::Seconds(6 * HOUR),
+    TimeStep::Seconds(12 * HOUR),
+    TimeStep::Days(1),
+    TimeStep::Days(2),
+    TimeStep::Days(7),
+    TimeStep::Days(14),
+    TimeStep::Months(1),
+    TimeStep::Months(3),
+    TimeStep::Months(6),
+    TimeStep::Months(12),
+    TimeStep::Months(24),
+    TimeStep::Months(60),
+    TimeStep::Months(120),
+];
+
+impl TimeStep {
+    /// Roughly how long the step is, for picking one which fits the range.
+    fn approx_seconds(self) -> i64 {
+        match self {
+            TimeStep::Seconds(s) => s,
+            TimeStep::Days(d) => d * DAY,
+            // The average length of a Gregorian month.
+            TimeStep::Months(m) => m as i64 * 2_629_746,
+        }
+    }
+
+    fn label_format(self) -> &'static str {
+        match self {
+            TimeStep::Seconds(s) if s < MINUTE => "%H:%M:%S",
+            TimeStep::Seconds(_) => "%H:%M",
+            TimeStep::Days(_) => "%b %d",
+            TimeStep::Months(m) if m < 12 => "%b %Y",
+            TimeStep::Months(_) => "%Y",
+        }
+    }
+}
+
+/// Ticks on round calendar units (minutes, hours, days, months...) for a range
+/// of timestamps in seconds since the Unix epoch, in UTC. At most `max_ticks`
+/// are returned, as long as the range is under a few centuries.
+pub fn time_ticks(min: f64, max: f64, max_ticks: usize) -> Vec<Tick> {
+    let max_ticks = max_ticks.max(1) as f64;
+    let range = max - min;
+    let step = TIME_STEPS
+        .iter()
+        .copied()
+        .find(|step| range / (step.approx_seconds() as f64) < max_ticks)
+        .unwrap_or(TimeStep::Months(1200));
+
+    let times = match step {
+        TimeStep::Seconds(s) => fixed_steps(min, max, s),
+        // Weeks start on a Monday; 1970-01-05 was one.
+        TimeStep::Days(7) | TimeStep::Days(14) => {
+            let monday = 4 * DAY;
+            fixed_steps(
+                min - monday as f64,
+                max - monday as f64,
+                step.approx_seconds(),
+            )
+            .into_iter()
+            .map(|t| t + monday)
+            .collect()
+        }
+        TimeStep::Days(d) => fixed_steps(min, max, d * DAY),
+        TimeStep::Months(m) => month_steps(min, max, m),
+    };
+
+    times
+        .into_iter()
+        .filter_map(|t| {
+            let dt = DateTime::<Utc>::from_timestamp(t, 0)?;
+            // Ticks within a day show the date when they pass midnight.
+            let format = match step {
+                TimeStep::Seconds(_) if t.rem_euclid(DAY) == 0 => "%b %d",
+                _ => step.label_format(),
+            };
+            Some(Tick {
+                value: t as f64,
+                label: dt.format(format).to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Multiples of `step` seconds between min and max.
+fn fixed_steps(min: f64, max: f64, step: i64) -> Vec<i64> {
+    let mut t = (min / step as f64).ceil() as i64 * step;
+    let mut times = Vec::new();
+    while t as f64 <= max {
+        times.push(t);
+        t += step;
+    }
+
+    times
+}
+
+/// The first of every `step`th month between min and max. Steps of a year or more
+/// start in January.
+fn month_steps(min: f64, max: f64, step: u32) -> Vec<i64> {
+    let Some(start) = DateTime::<Utc>::from_timestamp(min.floor() as i64, 0) else {
+        return Vec::new();
+    };
+
+    // Count months from year 0 so steps line up with the calendar.
+    let mut month = start.year() as i64 * 12 + start.month0() as i64;
+    month -= month.rem_euclid(step as i64);
+
+    let mut times = Vec::new();
+    loop {
+        let date = NaiveDate::from_ymd_opt(
+            month.div_euclid(12) as i32,
+            month.rem_euclid(12) as u32 + 1,
+            1,
+        );
+        let Some(t) = date
+            .and_then(|d| d.and_hms_opt(0, 0, 0))
+            .map(|d| d.and_utc().timestamp())
+        else {
+            break;
+        };
+        if t as f64 > max {
+            break;
+        }
+        if t as f64 >= min {
+            times.push(t);
+        }
+        month += step as i64;
+    }
+
+    times
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const OCT_17: f64 = 1_792_195_200.0;
+
+    fn labels(ticks: &[Tick]) -> Vec<&str> {
+        ticks.iter().map(|t| t.label.as_str()).collect()
+    }
+
+    #[test]
+    fn nice_steps() {
+        assert_eq!(nice_step(0.3), 0.5);
+        assert_eq!(nice_step(1.0), 1.0);
+        assert_eq!(nice_step(1.2), 2.0);
+        assert_eq!(nice_step(36.0), 50.0);
+        assert_eq!(nice_step(0.07), 0.1);
+    }
+
+    #[test]
+    fn nice() {
+        let ticks = nice_ticks(0.0, 360.0, 8);
+        assert_eq!(
+            labels(&ticks),
+            ["0", "50", "100", "150", "200", "250", "300", "350"]
+        );
+    }
+
+    #[test]
+    fn nice_fractions() {
+        let ticks = nice_ticks(-1.0, 1.0, 4);
+        assert_eq!(labels(&ticks), ["-1.0", "-0.5", "0.0", "0.5", "1.0"]);
+
+        let ticks = nice_ticks(-0.25, 0.25, 5);
+        assert_eq!(labels(&ticks), ["-0.2", "-0.1", "0.0", "0.1", "0.2"]);
+    }
+
+    #[test]
+    fn log() {
+        let ticks = log_ticks(1.0, 12000.0, 8);
+        assert_eq!(labels(&ticks), ["1", "10", "100", "1000", "10000"]);
+
+        let ticks = log_ticks(0.01, 1e8, 3);
+        assert_eq!(labels(&ticks), ["0.01", "100", "1000000"]);
+
+        let ticks = log_ticks(2.0, 8.0, 4);
+        assert_eq!(labels(&ticks), ["2", "4", "6", "8"]);
+    }
+
+    #[test]
+    fn hours() {
+        let ticks = time_ticks(OCT_17 + 1800.0, OCT_17 + 6.0 * 3600.0, 6);
+        assert_eq!(
+            labels(&ticks),
+            ["01:00", "02:00", "03:00", "04:00", "05:00", "06:00"]
+        );
+        assert_eq!(ticks[0].value, OCT_17 + 3600.0);
+    }
+
+    #[test]
+    fn minutes() {
+        let ticks = time_ticks(OCT_17 + 60.0, OCT_17 + 1800.0, 4);
+        assert_eq!(labels(&ticks), ["00:15", "00:30"]);
+    }
+
+    #[test]
+    fn hours_across_midnight() {
+        let ticks = time_ticks(OCT_17 - 7.0 * 3600.0, OCT_17 + 6.0 * 3600.0, 5);
+        assert_eq!(
+            labels(&ticks),
+            ["18:00", "21:00", "Oct 17", "03:00", "06:00"]
+        );
+    }
+
+    #[test]
+    fn days() {
+        let ticks = time_ticks(OCT_17, OCT_17 + 2.5 * 86400.0, 5);
+        assert_eq!(labels(&ticks), ["Oct 17", "Oct 18", "Oct 19"]);
+    }
+
+    #[test]
+    fn weeks_start_on_monday() {
+        let ticks = time_ticks(OCT_17, OCT_17 + 30.0 * 86400.0, 5);
+        // 2026-10-19 is a Monday.
+        assert_eq!(
+            labels(&ticks),
+            ["Oct 19", "Oct 26", "Nov 02", "Nov 09", "Nov 16"]
+        );
+    }
+
+    #[test]
+    fn months() {
+        let ticks = time_ticks(OCT_17, OCT_17 + 200.0 * 86400.0, 3);
+        assert_eq!(labels(&ticks), ["Jan 2027", "Apr 2027"]);
+    }
+
+    #[test]
+    fn years() {
+        let ticks = time_ticks(OCT_17, OCT_17 + 4.0 * 365.0 * 86400.0, 4);
+        assert_eq!(labels(&ticks), ["2027", "2028", "2029", "2030"]);
+    }
+}
@@ -0,0 +1,177 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+use crate::config::TimeFormat;
+
+/// Date-time layouts tried, longest first, when the format is `auto`. Times without
+/// an offset are taken to be in UTC.
+const AUTO_DATETIME_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y/%m/%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M",
+    "%Y/%m/%d %H:%M",
+];
+
+const AUTO_DATE_FORMATS: &[&str] = &["%Y-%m-%d", "%Y/%m/%d"];
+
+/// Parse a timestamp at the start of `s`, returning it as seconds since the Unix
+/// epoch along with the rest of the string.
+pub fn parse_time_prefix<'a>(s: &'a str, format: &TimeFormat) -> Option<(f64, &'a str)> {
+    match format {
+        TimeFormat::Auto => parse_auto(s),
+        TimeFormat::Epoch => parse_epoch(s, 1.0),
+        TimeFormat::EpochMillis => parse_epoch(s, 1000.0),
+        TimeFormat::Pattern(pattern) => parse_pattern(s, pattern),
+    }
+}
+
+/// Parse a field holding only a timestamp.
+pub fn parse_time(s: &str, format: &TimeFormat) -> Option<f64> {
+    match parse_time_prefix(s.trim(), format)? {
+        (t, "") => Some(t),
+        _ => None,
+    }
+}
+
+fn parse_auto(s: &str) -> Option<(f64, &str)> {
+    // RFC 3339, and ISO 8601 with an offset.
+    if let Ok((dt, rest)) = DateTime::parse_and_remainder(s, "%+") {
+        return Some((seconds(dt.timestamp(), dt.timestamp_subsec_nanos()), rest));
+    }
+
+    parse_naive(s, AUTO_DATETIME_FORMATS, AUTO_DATE_FORMATS)
+}
+
+fn parse_pattern<'a>(s: &'a str, pattern: &str) -> Option<(f64, &'a str)> {
+    if let Ok((dt, rest)) = DateTime::parse_and_remainder(s, pattern) {
+        return Some((seconds(dt.timestamp(), dt.timestamp_subsec_nanos()), rest));
+    }
+
+    parse_naive(s, &[pattern], &[pattern])
+}
+
+fn parse_naive<'a>(
+    s: &'a str,
+    datetime_formats: &[&str],
+    date_formats: &[&str],
+) -> Option<(f64, &'a str)> {
+    for format in datetime_formats {
+        if let Ok((dt, rest)) = NaiveDateTime::parse_and_remainder(s, format) {
+            let dt = dt.and_utc();
+            return Some((seconds(dt.timestamp(), dt.timestamp_subsec_nanos()), rest));
+        }
+    }
+
+    for format in date_formats {
+        if let Ok((date, rest)) = NaiveDate::parse_and_remainder(s, format) {
+            let dt = date.and_hms_opt(0, 0, 0)?.and_utc();
+            return Some((dt.timestamp() as f64, rest));
+        }
+    }
+
+    None
+}
+
+fn parse_epoch(s: &str, per_second: f64) -> Option<(f64, &str)> {
+    // Only the first character may be a sign, so a following field such as
+    // "-5" isn't taken as part of the number.
+    let unsigned = s.strip_prefix(['-', '+']).unwrap_or(s);
+    let end = unsigned
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(unsigned.len());
+    let (number, rest) = s.split_at(s.len() - unsigned.len() + end);
+    let t = number.parse::<f64>().ok()?;
+
+    Some((t / per_second, rest))
+}
+
+fn seconds(secs: i64, nanos: u32) -> f64 {
+    secs as f64 + nanos as f64 / 1e9
+}
+
+/// Format seconds since the Unix epoch as a UTC date, leaving off the time of day
+/// or fractional seconds when they are zero.
+pub fn format_time(t: f64) -> String {
+    let secs = t.floor();
+    let nanos = ((t - secs) * 1e9).round() as u32;
+    let Some(dt) = Utc
+        .timestamp_opt(secs as i64, nanos.min(999_999_999))
+        .single()
+    else {
+        return t.to_string();
+    };
+
+    let format = if nanos >= 1_000_000 {
+        "%Y-%m-%d %H:%M:%S%.3f"
+    } else if secs.rem_euclid(86400.0) == 0.0 {
+        "%Y-%m-%d"
+    } else {
+        "%Y-%m-%d %H:%M:%S"
+    };
+
+    dt.format(format).to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_rfc3339() {
+        let t = parse_time("2026-10-17T12:00:00Z", &TimeFormat::Auto);
+        assert_eq!(t, Some(1_792_238_400.0));
+        let t = parse_time("2026-10-17T14:00:00+02:00", &TimeFormat::Auto);
+        assert_eq!(t, Some(1_792_238_400.0));
+        let t = parse_time("2026-10-17T12:00:00.250Z", &TimeFormat::Auto);
+        assert_eq!(t, Some(1_792_238_400.25));
+    }
+
+    #[test]
+    fn parse_iso8601_without_offset() {
+        let t = parse_time("2026-10-17 12:00:00", &TimeFormat::Auto);
+        assert_eq!(t, Some(1_792_238_400.0));
+        let t = parse_time("2026-10-17T12:00", &TimeFormat::Auto);
+        assert_eq!(t, Some(1_792_238_400.0));
+        let t = parse_time("2026/10/17", &TimeFormat::Auto);
+        assert_eq!(t, Some(1_792_195_200.0));
+        assert_eq!(parse_time("web01", &TimeFormat::Auto), None);
+    }
+
+    #[test]
+    fn parse_epoch_units() {
+        let t = parse_time("1792238400", &TimeFormat::Epoch);
+        assert_eq!(t, Some(1_792_238_400.0));
+        let t = parse_time("1792238400500", &TimeFormat::EpochMillis);
+        assert_eq!(t, Some(1_792_238_400.5));
+    }
+
+    #[test]
+    fn parse_epoch_sign_only_first() {
+        let t = parse_time_prefix("-86400-5", &TimeFormat::Epoch);
+        assert_eq!(t, Some((-86_400.0, "-5")));
+        let t = parse_time_prefix("+60+1", &TimeFormat::Epoch);
+        assert_eq!(t, Some((60.0, "+1")));
+    }
+
+    #[test]
+    fn parse_user_pattern() {
+        let format = TimeFormat::Pattern("%d/%m/%Y %H:%M".to_string());
+        let t = parse_time("17/10/2026 12:00", &format);
+        assert_eq!(t, Some(1_792_238_400.0));
+    }
+
+    #[test]
+    fn parse_prefix_leaves_rest() {
+        let (t, rest) = parse_time_prefix("2026-10-17T12:00:00Z,42", &TimeFormat::Auto).unwrap();
+        assert_eq!(t, 1_792_238_400.0);
+        assert_eq!(rest, ",42");
+    }
+
+    #[test]
+    fn format_precision() {
+        assert_eq!(format_time(1_792_195_200.0), "2026-10-17");
+        assert_eq!(format_time(1_792_238_400.0), "2026-10-17 12:00:00");
+        assert_eq!(format_time(1_792_238_400.25), "2026-10-17 12:00:00.250");
+    }
+}
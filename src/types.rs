@@ -1,7 +1,7 @@
 use crate::config::Config;
 use crate::scale::TransformType;
 
-pub const EMPTY_VALUE: f64 = std::f64::NAN;
+pub const EMPTY_VALUE: f64 = f64::NAN;
 
 #[derive(Debug, Clone, Copy)]
 pub struct Point(pub f64, pub f64);
@@ -52,18 +52,82 @@ impl Point {
     }
 }
 
-#[derive(Debug)]
-#[derive(Default)]
+#[derive(Debug, Default, Clone)]
 pub struct DataSet {
     pub columns: usize,
     pub rows: usize,
     pub points: Vec<Vec<Point>>, // p[column][row]
+    pub header: Option<Vec<String>>,
+    pub fields: Vec<usize>, // fields[column] = index of the input field
 }
 
+impl DataSet {
+    /// Build a dataset from columns of points, padding shorter columns
+    /// with empty values.
+    pub fn from_columns(points: Vec<Vec<Point>>) -> Self {
+        let mut dataset = DataSet {
+            columns: points.len(),
+            rows: points.iter().map(|c| c.len()).max().unwrap_or(0),
+            points,
+            ..Default::default()
+        };
 
+        for column in dataset.points.iter_mut() {
+            column.resize(dataset.rows, Point(EMPTY_VALUE, EMPTY_VALUE));
+        }
+
+        dataset
+    }
+
+    /// The input field a column was read from, counting from 0.
+    pub fn field(&self, col: usize) -> Option<usize> {
+        // Datasets built in code have no field mapping, so columns are fields.
+        if self.fields.is_empty() {
+            Some(col)
+        } else {
+            self.fields.get(col).copied()
+        }
+    }
+
+    /// The name of a column, if the input had a header row.
+    pub fn column_name(&self, col: usize) -> Option<&str> {
+        self.header
+            .as_ref()?
+            .get(self.field(col)?)
+            .map(String::as_str)
+    }
+
+    /// The name of a column where the input had a header, and otherwise the
+    /// input field it came from.
+    pub fn column_label(&self, col: usize) -> String {
+        match self.column_name(col) {
+            Some(name) => name.to_string(),
+            None => format!("column {}", self.field(col).unwrap_or(col) + 1),
+        }
+    }
+
+    /// Append a row of values which share an x value.
+    pub fn push_row(&mut self, x: f64, values: &[f64]) {
+        let row = self.rows;
+        for (col, &y) in values.iter().enumerate() {
+            self.add_point(row, col, Point(x, y));
+        }
+
+        // Columns missing from this row are padded with empty values.
+        for col in values.len()..self.columns {
+            self.add_point(row, col, Point(x, EMPTY_VALUE));
+        }
+    }
 
-impl DataSet {
     pub fn add_pair(&mut self, config: &Config, row: usize, col: usize, point: Point) {
+        if config.flip_xy {
+            self.add_point(row, col, Point(point.1, point.0));
+        } else {
+            self.add_point(row, col, point);
+        }
+    }
+
+    fn add_point(&mut self, row: usize, col: usize, point: Point) {
         // Add columns, padding with None as necessary
         while col >= self.columns {
             let mut v = Vec::with_capacity(self.rows);
@@ -83,10 +147,6 @@ impl DataSet {
             self.rows += 1;
         }
 
-        if config.flip_xy {
-            self.points[col][row] = Point(point.1, point.0);
-        } else {
-            self.points[col][row] = point;
-        }
+        self.points[col][row] = point;
     }
 }
@@ -1,6 +1,8 @@
 #[test]
 fn cli_tests() {
     trycmd::TestCases::new()
+        // Keep the tests clear of any config file the user has.
+        .env("XDG_CONFIG_HOME", "tests/no-config")
         .case("tests/cmd/*.toml")
         .case("tests/cmd/*.md");
 }